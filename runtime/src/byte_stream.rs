@@ -0,0 +1,198 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of `Stream` for `&'a [u8]` type. It is used by grammars working on bytes, such as binary formats, where the atoms are of type `u8` instead of `char`.
+
+use stream::*;
+use std::cmp::{Ordering, min};
+use std::ascii;
+use super::*;
+
+impl<'a> Stream for &'a [u8]
+{
+  type Output = ByteStream<'a>;
+  fn stream(self) -> ByteStream<'a> {
+    ByteStream::new(self)
+  }
+}
+
+impl<'a> Stream for &'a Vec<u8>
+{
+  type Output = ByteStream<'a>;
+  fn stream(self) -> ByteStream<'a> {
+    self.as_slice().stream()
+  }
+}
+
+/// Represents a stream from a `&'a [u8]`.
+#[derive(Clone)]
+pub struct ByteStream<'a>
+{
+  raw_data: &'a [u8],
+  offset: usize
+}
+
+impl<'a> ByteStream<'a>
+{
+  fn new(raw_data: &'a [u8]) -> ByteStream<'a> {
+    ByteStream {
+      raw_data: raw_data,
+      offset: 0
+    }
+  }
+
+  #[inline(always)]
+  fn assert_same_raw_data(&self, other: &ByteStream<'a>) {
+    debug_assert!(self.raw_data.as_ptr() == other.raw_data.as_ptr(),
+      "Operations between two streams are only defined when they share the same raw data.");
+  }
+
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+}
+
+impl<'a> Iterator for ByteStream<'a>
+{
+  type Item = u8;
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.offset < self.raw_data.len() {
+      let current = self.raw_data[self.offset];
+      self.offset += 1;
+      Some(current)
+    } else {
+      None
+    }
+  }
+}
+
+impl<'a> PartialEq for ByteStream<'a>
+{
+  fn eq(&self, other: &Self) -> bool {
+    self.assert_same_raw_data(other);
+    self.offset == other.offset
+  }
+}
+
+impl<'a> Eq for ByteStream<'a> {}
+
+impl<'a> PartialOrd for ByteStream<'a>
+{
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.assert_same_raw_data(other);
+    self.offset.partial_cmp(&other.offset)
+  }
+}
+
+impl<'a> Ord for ByteStream<'a>
+{
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.assert_same_raw_data(other);
+    self.offset.cmp(&other.offset)
+  }
+}
+
+/// There is no line in a stream of bytes, so the location is the offset of the current byte.
+impl<'a> Location for ByteStream<'a>
+{
+  fn location(&self) -> String {
+    format!("byte {}", self.offset)
+  }
+}
+
+/// The bytes are escaped such that non-printable bytes are displayed as `\xNN`.
+impl<'a> CodeSnippet for ByteStream<'a>
+{
+  fn code_snippet(&self, len_hint: usize) -> String {
+    let total_len = self.raw_data.len();
+    let current_offset = self.offset;
+    if current_offset == total_len {
+      String::from("<end-of-file>")
+    }
+    else {
+      let len = min(total_len - current_offset, len_hint);
+      self.raw_data[current_offset..][..len].iter()
+        .flat_map(|&byte| ascii::escape_default(byte))
+        .map(|byte| byte as char)
+        .collect()
+    }
+  }
+}
+
+impl<'a> ConsumePrefix<&'static [u8]> for ByteStream<'a>
+{
+  fn consume_prefix(&mut self, prefix: &'static [u8]) -> bool {
+    let current_offset = self.offset;
+    let end_offset = current_offset + prefix.len();
+    if end_offset <= self.raw_data.len()
+     && &self.raw_data[current_offset..end_offset] == prefix
+    {
+      self.offset = end_offset;
+      true
+    } else {
+      false
+    }
+  }
+}
+
+impl<'a> HasNext for ByteStream<'a>
+{
+  fn has_next(&self) -> bool {
+    self.offset < self.raw_data.len()
+  }
+}
+
+impl<'a> StreamSpan for Range<ByteStream<'a>>
+{
+  type Output = Span;
+  fn stream_span(&self) -> Self::Output {
+    make_span(
+      self.start.offset,
+      self.end.offset)
+  }
+}
+
+impl<'a> StreamSlice for Range<ByteStream<'a>>
+{
+  type Output = &'a [u8];
+  fn stream_slice(&self) -> Self::Output {
+    &self.start.raw_data[self.start.offset..self.end.offset]
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_consume_prefix() {
+    let data: &[u8] = b"\x00ab";
+    let mut s1 = data.stream();
+    assert!(!s1.consume_prefix(&b"ab"[..]));
+    assert!(s1.consume_prefix(&b"\x00a"[..]));
+    assert_eq!(s1.next(), Some(b'b'));
+    assert_eq!(s1.next(), None);
+  }
+
+  #[test]
+  fn test_code_snippet() {
+    let data: &[u8] = b"\x01OAK\xff";
+    let s1 = data.stream();
+    assert_eq!(s1.code_snippet(3), "\\x01OA");
+    let mut s2 = s1.clone();
+    s2.consume_prefix(&b"\x01OAK\xff"[..]);
+    assert_eq!(s2.code_snippet(3), "<end-of-file>");
+    assert_eq!(s2.location(), "byte 5");
+  }
+}
@@ -19,13 +19,19 @@
 extern crate syntex_pos;
 
 pub use str_stream::*;
+pub use byte_stream::*;
+pub use token_stream::*;
 pub use stream::*;
 pub use parse_state::*;
+pub use precedence::*;
 use syntex_pos::{BytePos, mk_sp};
 
 pub mod str_stream;
+pub mod byte_stream;
+pub mod token_stream;
 pub mod parse_state;
 pub mod stream;
+pub mod precedence;
 pub mod file_map_stream;
 
 pub fn make_span(lo: usize, hi: usize) -> Span {
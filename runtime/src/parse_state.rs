@@ -124,7 +124,17 @@ pub struct ParseState<S, T>
   /// The current stream that can be partially or fully consumed.
   pub current: S,
   /// Contains the AST if the current state is successful and `None` if it is erroneous.
-  pub data: Option<T>
+  pub data: Option<T>,
+  /// Indentation stack used by `indent`, `dedent` and `samedent`. The top of the stack is the width of the current block, it is `0` when the stack is empty.
+  pub indentation: Vec<usize>
+}
+
+/// A position in the parsing state that can be restored on backtracking. Besides the stream position, it saves the indentation stack since it is modified by the parsing.
+#[derive(Clone)]
+pub struct ParseMark<S>
+{
+  pub position: S,
+  indentation: Vec<usize>
 }
 
 impl<S, T> ParseState<S, T> where
@@ -137,7 +147,8 @@ impl<S, T> ParseState<S, T> where
       expected: vec![],
       failed: false,
       current: stream,
-      data: None
+      data: None,
+      indentation: vec![]
     }
   }
 
@@ -169,7 +180,8 @@ impl<S, T> ParseState<S, T> where
       expected: self.expected,
       failed: false,
       current: self.current,
-      data: Some(data)
+      data: Some(data),
+      indentation: self.indentation
     }
   }
 
@@ -179,7 +191,8 @@ impl<S, T> ParseState<S, T> where
       expected: self.expected,
       failed: true,
       current: self.current,
-      data: None
+      data: None,
+      indentation: self.indentation
     }
   }
 
@@ -188,11 +201,17 @@ impl<S, T> ParseState<S, T> where
     self.current.clone()
   }
 
+  /// The current position in the stream, it is used for building spans.
+  pub fn position(&self) -> S {
+    self.current.clone()
+  }
+
   pub fn restore_from_failure(self, mark: S) -> ParseState<S, ()> {
     assert!(self.failed, "Restoring a successful ParseState is not allowed.");
     self.restore(mark)
   }
 
+  /// Restores the stream position `mark`, the indentation stack is left unchanged. Use `checkpoint` and `restore_checkpoint` to restore it too.
   pub fn restore(self, mark: S) -> ParseState<S, ()> {
     assert!(self.data.is_none(), "Restoring a ParseState with data is not allowed.");
     ParseState {
@@ -200,10 +219,32 @@ impl<S, T> ParseState<S, T> where
       expected: self.expected,
       failed: false,
       current: mark,
-      data: None
+      data: None,
+      indentation: self.indentation
+    }
+  }
+
+  /// Same as `mark` but the indentation stack is saved too, it is used by the generated parsers for backtracking.
+  pub fn checkpoint(&self) -> ParseMark<S> {
+    assert!(!self.failed, "Marking a failed ParseState is not allowed.");
+    ParseMark {
+      position: self.current.clone(),
+      indentation: self.indentation.clone()
     }
   }
 
+  pub fn restore_checkpoint_from_failure(self, mark: ParseMark<S>) -> ParseState<S, ()> {
+    assert!(self.failed, "Restoring a successful ParseState is not allowed.");
+    self.restore_checkpoint(mark)
+  }
+
+  pub fn restore_checkpoint(self, mark: ParseMark<S>) -> ParseState<S, ()> {
+    let indentation = mark.indentation;
+    let mut state = self.restore(mark.position);
+    state.indentation = indentation;
+    state
+  }
+
   /// Transforms `self` into a more usable `ParseResult` value. It is useful when the state is terminal or if the state will not be further transformed.
   pub fn into_result(self) -> ParseResult<S, T> {
     let expectation = ParseExpectation::new(self.farthest_read, self.expected);
@@ -232,7 +273,8 @@ impl<S, T> ParseState<S, T> where
       expected: self.expected,
       failed: self.failed,
       current: self.current,
-      data: None
+      data: None,
+      indentation: self.indentation
     };
     (state, data)
   }
@@ -250,6 +292,61 @@ impl<S> ParseState<S, ()>
   }
 }
 
+/// Indentation primitives. The indentation of a line is the number of spaces and tabulations starting from the current position, which should be the beginning of a line.
+impl<S, T> ParseState<S, T> where
+ S: Iterator<Item=char> + Clone
+{
+  fn current_block(&self) -> usize {
+    self.indentation.last().cloned().unwrap_or(0)
+  }
+
+  /// Returns the width of the indentation and the stream positioned right after it.
+  fn read_indentation(&self) -> (usize, S) {
+    let mut width = 0;
+    let mut after = self.current.clone();
+    let mut next = after.clone();
+    while let Some(c) = next.next() {
+      if c != ' ' && c != '\t' {
+        break;
+      }
+      width += 1;
+      after = next.clone();
+    }
+    (width, after)
+  }
+
+  /// Succeeds if the indentation is deeper than the current block, it is consumed and opens a new block.
+  pub fn indent(&mut self) -> bool {
+    let (width, after) = self.read_indentation();
+    if width > self.current_block() {
+      self.indentation.push(width);
+      self.current = after;
+      true
+    }
+    else { false }
+  }
+
+  /// Succeeds if the indentation is the one of the current block, it is consumed.
+  pub fn samedent(&mut self) -> bool {
+    let (width, after) = self.read_indentation();
+    if width == self.current_block() {
+      self.current = after;
+      true
+    }
+    else { false }
+  }
+
+  /// Closes the current block without consuming the stream, it fails if no block is opened or if the indentation of the next line is not less than the one of the current block.
+  pub fn dedent(&mut self) -> bool {
+    let (width, _) = self.read_indentation();
+    if !self.indentation.is_empty() && width < self.current_block() {
+      self.indentation.pop();
+      true
+    }
+    else { false }
+  }
+}
+
 impl<S, T, I> Iterator for ParseState<S, T> where
  S: Iterator<Item=I>
 {
@@ -266,3 +363,35 @@ impl<S, T, P> ConsumePrefix<P> for ParseState<S, T> where
     self.current.consume_prefix(prefix)
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use str_stream::*;
+
+  #[test]
+  fn test_indentation() {
+    let mut state: ParseState<StrStream, ()> = "  a\n  b".into_state();
+    assert!(!state.samedent());
+    let mark = state.checkpoint();
+    assert!(state.indent());
+    assert_eq!(state.next(), Some('a'));
+    assert_eq!(state.next(), Some('\n'));
+    assert!(!state.indent());
+    // The next line is still in the current block.
+    assert!(!state.dedent());
+    assert!(state.samedent());
+    assert_eq!(state.next(), Some('b'));
+    assert!(state.dedent());
+    assert!(!state.dedent());
+    // `restore` only restores the position.
+    state.indentation.push(4);
+    let position = state.mark();
+    let mut state = state.restore(position);
+    assert_eq!(state.indentation, vec![4]);
+    // The indentation stack is restored on backtracking.
+    state.failed = true;
+    let state = state.restore_checkpoint_from_failure(mark);
+    assert!(state.indentation.is_empty());
+  }
+}
@@ -0,0 +1,178 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Operator-precedence climbing used by the code generated for precedence expressions such as `precedence factor { left add_op > bin_expr, right exp_op > bin_expr }`.
+//!
+//! Operands and operators are pushed in the order they are read from the stream. Operators are first _pending_: they are only committed when the operand following them has been parsed, so a parser can backtrack on an operator that is not followed by an operand by simply dropping the pending operators. Levels are numbered from the loosest (`0`) to the tightest binding.
+
+enum Operator<'a, T>
+{
+  Prefix(usize, Box<FnMut(T) -> T + 'a>),
+  Infix(usize, bool, Box<FnMut(T, T) -> T + 'a>)
+}
+
+impl<'a, T> Operator<'a, T>
+{
+  fn level(&self) -> usize {
+    match self {
+      &Operator::Prefix(level, _) => level,
+      &Operator::Infix(level, _, _) => level
+    }
+  }
+}
+
+pub struct PrecedenceClimbing<'a, T>
+{
+  operands: Vec<T>,
+  operators: Vec<Operator<'a, T>>,
+  pending: Vec<Operator<'a, T>>
+}
+
+impl<'a, T> PrecedenceClimbing<'a, T>
+{
+  pub fn new() -> PrecedenceClimbing<'a, T> {
+    PrecedenceClimbing {
+      operands: vec![],
+      operators: vec![],
+      pending: vec![]
+    }
+  }
+
+  /// Registers a pending prefix operator of value `op`, the node is built with `action(op, operand)`.
+  pub fn prefix<O: 'a>(&mut self, level: usize, op: O, action: fn(O, T) -> T) {
+    let mut op = Some(op);
+    self.pending.push(Operator::Prefix(level,
+      Box::new(move |operand| action(op.take().unwrap(), operand))));
+  }
+
+  /// Registers a pending left-associative infix operator, the node is built with `action(lhs, op, rhs)`.
+  pub fn infix_left<O: 'a>(&mut self, level: usize, op: O, action: fn(T, O, T) -> T) {
+    self.infix(level, false, op, action);
+  }
+
+  /// Registers a pending right-associative infix operator, the node is built with `action(lhs, op, rhs)`.
+  pub fn infix_right<O: 'a>(&mut self, level: usize, op: O, action: fn(T, O, T) -> T) {
+    self.infix(level, true, op, action);
+  }
+
+  fn infix<O: 'a>(&mut self, level: usize, right_assoc: bool, op: O, action: fn(T, O, T) -> T) {
+    let mut op = Some(op);
+    self.pending.push(Operator::Infix(level, right_assoc,
+      Box::new(move |lhs, rhs| action(lhs, op.take().unwrap(), rhs))));
+  }
+
+  /// Applies a postfix operator on the last operand once the operators binding tighter than `level` have been reduced. The node is built with `action(operand, op)`.
+  pub fn postfix<O>(&mut self, level: usize, op: O, action: fn(T, O) -> T) {
+    self.reduce_while(|top| top.level() >= level);
+    let operand = self.operands.pop().expect("A postfix operator must follow an operand.");
+    self.operands.push(action(operand, op));
+  }
+
+  /// Commits the pending operators and pushes `operand`.
+  pub fn operand(&mut self, operand: T) {
+    let pending: Vec<_> = self.pending.drain(..).collect();
+    for op in pending {
+      if let Operator::Infix(level, right_assoc, _) = op {
+        self.reduce_while(|top| top.level() > level
+          || (top.level() == level && !right_assoc));
+      }
+      self.operators.push(op);
+    }
+    self.operands.push(operand);
+  }
+
+  /// Drops the pending operators (they were not followed by an operand) and builds the final value.
+  pub fn finish(mut self) -> T {
+    self.pending.clear();
+    self.reduce_while(|_| true);
+    assert!(self.operands.len() == 1,
+      "A precedence expression must reduce to exactly one operand.");
+    self.operands.pop().unwrap()
+  }
+
+  fn reduce_while<F>(&mut self, cond: F) where
+   F: Fn(&Operator<'a, T>) -> bool
+  {
+    while self.operators.last().map_or(false, |top| cond(top)) {
+      match self.operators.pop().unwrap() {
+        Operator::Prefix(_, mut action) => {
+          let operand = self.operands.pop().unwrap();
+          self.operands.push(action(operand));
+        }
+        Operator::Infix(_, _, mut action) => {
+          let rhs = self.operands.pop().unwrap();
+          let lhs = self.operands.pop().unwrap();
+          self.operands.push(action(lhs, rhs));
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn bin(lhs: String, op: char, rhs: String) -> String {
+    format!("({}{}{})", lhs, op, rhs)
+  }
+
+  fn neg(_op: (), operand: String) -> String {
+    format!("(-{})", operand)
+  }
+
+  fn fact(operand: String, _op: ()) -> String {
+    format!("({}!)", operand)
+  }
+
+  #[test]
+  fn test_associativity() {
+    // 1 - 2 - 3 ^ 4 ^ 5 * 6
+    let mut climbing = PrecedenceClimbing::new();
+    climbing.operand(format!("1"));
+    climbing.infix_left(0, '-', bin);
+    climbing.operand(format!("2"));
+    climbing.infix_left(0, '-', bin);
+    climbing.operand(format!("3"));
+    climbing.infix_right(2, '^', bin);
+    climbing.operand(format!("4"));
+    climbing.infix_right(2, '^', bin);
+    climbing.operand(format!("5"));
+    climbing.infix_left(1, '*', bin);
+    climbing.operand(format!("6"));
+    assert_eq!(climbing.finish(), "((1-2)-((3^(4^5))*6))");
+  }
+
+  #[test]
+  fn test_unary_operators() {
+    // -1 + 2! with `-` binding looser than `+`.
+    let mut climbing = PrecedenceClimbing::new();
+    climbing.prefix(0, (), neg);
+    climbing.operand(format!("1"));
+    climbing.infix_left(1, '+', bin);
+    climbing.operand(format!("2"));
+    climbing.postfix(2, (), fact);
+    assert_eq!(climbing.finish(), "(-(1+(2!)))");
+  }
+
+  #[test]
+  fn test_pending_operators() {
+    // `1 + -` followed by something that is not an operand.
+    let mut climbing = PrecedenceClimbing::new();
+    climbing.operand(format!("1"));
+    climbing.infix_left(0, '+', bin);
+    climbing.prefix(1, (), neg);
+    assert_eq!(climbing.finish(), "1");
+  }
+}
@@ -179,6 +179,15 @@ impl<'a> StreamSpan for Range<StrStream<'a>>
   }
 }
 
+impl<'a> StreamSlice for Range<StrStream<'a>>
+{
+  type Output = &'a str;
+  fn stream_slice(&self) -> Self::Output {
+    self.start.assert_same_raw_data(&self.end);
+    &self.start.raw_data[self.start.bytes_offset..self.end.bytes_offset]
+  }
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -231,6 +240,15 @@ mod test {
     test_str_stream(abc.stream(), abc.chars());
   }
 
+  #[test]
+  fn test_stream_slice() {
+    let start = "let x".stream();
+    let mut end = start.clone();
+    assert!(end.consume_prefix("let"));
+    assert_eq!(Range { start: start.clone(), end: end.clone() }.stream_slice(), "let");
+    assert_eq!(Range { start: end.clone(), end: end }.stream_slice(), "");
+  }
+
   #[test]
   fn test_empty_stream() {
     let mut empty = "".stream();
@@ -65,3 +65,10 @@ pub trait StreamSpan
   type Output;
   fn stream_span(&self) -> Self::Output;
 }
+
+/// Borrows the underlying data traversed between two positions of the same stream, for example the `&'a str` between two `StrStream<'a>`. It is used by the capture expression `$e`.
+pub trait StreamSlice
+{
+  type Output;
+  fn stream_slice(&self) -> Self::Output;
+}
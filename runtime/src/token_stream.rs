@@ -0,0 +1,212 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of `Stream` for a slice of tokens produced by an external lexer. The atoms of the grammar are the tokens themselves and they are matched with Rust patterns such as `Token::Ident(_)`.
+//!
+//! The location and the span of an expression are delegated to the tokens, which must carry their own span in the source.
+
+use stream::*;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use super::*;
+use syntex_pos::{BytePos, mk_sp};
+
+/// A token carrying its span in the source, it is usually produced by a lexer.
+pub trait TokenSpan
+{
+  fn token_span(&self) -> Span;
+}
+
+impl<'a, T> Stream for TokenStream<'a, T>
+{
+  type Output = TokenStream<'a, T>;
+  fn stream(self) -> TokenStream<'a, T> {
+    self
+  }
+}
+
+/// Represents a stream from a `&'a [T]` where `T` is the type of the tokens.
+pub struct TokenStream<'a, T: 'a>
+{
+  tokens: &'a [T],
+  offset: usize
+}
+
+impl<'a, T> TokenStream<'a, T>
+{
+  pub fn new(tokens: &'a [T]) -> TokenStream<'a, T> {
+    TokenStream {
+      tokens: tokens,
+      offset: 0
+    }
+  }
+
+  #[inline(always)]
+  fn assert_same_raw_data(&self, other: &TokenStream<'a, T>) {
+    debug_assert!(self.tokens.as_ptr() == other.tokens.as_ptr(),
+      "Operations between two streams are only defined when they share the same raw data.");
+  }
+
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  pub fn current_token(&self) -> Option<&'a T> {
+    self.tokens.get(self.offset)
+  }
+}
+
+// `derive(Clone)` would require `T: Clone`.
+impl<'a, T> Clone for TokenStream<'a, T>
+{
+  fn clone(&self) -> Self {
+    TokenStream {
+      tokens: self.tokens,
+      offset: self.offset
+    }
+  }
+}
+
+impl<'a, T> Iterator for TokenStream<'a, T> where
+ T: Clone
+{
+  type Item = T;
+  fn next(&mut self) -> Option<Self::Item> {
+    let current = self.current_token().cloned();
+    if current.is_some() {
+      self.offset += 1;
+    }
+    current
+  }
+}
+
+impl<'a, T> PartialEq for TokenStream<'a, T>
+{
+  fn eq(&self, other: &Self) -> bool {
+    self.assert_same_raw_data(other);
+    self.offset == other.offset
+  }
+}
+
+impl<'a, T> Eq for TokenStream<'a, T> {}
+
+impl<'a, T> PartialOrd for TokenStream<'a, T>
+{
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.assert_same_raw_data(other);
+    self.offset.partial_cmp(&other.offset)
+  }
+}
+
+impl<'a, T> Ord for TokenStream<'a, T>
+{
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.assert_same_raw_data(other);
+    self.offset.cmp(&other.offset)
+  }
+}
+
+/// The location of the current token, or the location of the last one if the stream is fully consumed.
+impl<'a, T> Location for TokenStream<'a, T> where
+ T: Location
+{
+  fn location(&self) -> String {
+    self.current_token()
+      .or(self.tokens.last())
+      .map_or(String::from("<empty>"), |token| token.location())
+  }
+}
+
+/// `len_hint` is the number of tokens displayed.
+impl<'a, T> CodeSnippet for TokenStream<'a, T> where
+ T: Debug
+{
+  fn code_snippet(&self, len_hint: usize) -> String {
+    if self.offset == self.tokens.len() {
+      String::from("<end-of-file>")
+    }
+    else {
+      let tokens: Vec<_> = self.tokens[self.offset..].iter()
+        .take(len_hint)
+        .map(|token| format!("{:?}", token))
+        .collect();
+      tokens.join(" ")
+    }
+  }
+}
+
+impl<'a, T> HasNext for TokenStream<'a, T>
+{
+  fn has_next(&self) -> bool {
+    self.offset < self.tokens.len()
+  }
+}
+
+/// The span starts at the first token of the range and ends at the last token read before `end`.
+impl<'a, T> StreamSpan for Range<TokenStream<'a, T>> where
+ T: TokenSpan
+{
+  type Output = Span;
+  fn stream_span(&self) -> Self::Output {
+    let tokens = self.start.tokens;
+    match (tokens.get(self.start.offset), self.end.offset) {
+      (Some(first), end) if end > self.start.offset => {
+        let last = &tokens[end - 1];
+        mk_sp(first.token_span().lo, last.token_span().hi)
+      }
+      // Empty range: the span is empty and located at the next token (or after the last one).
+      _ => {
+        let pos = tokens.get(self.start.offset)
+          .map(|token| token.token_span().lo)
+          .or(tokens.last().map(|token| token.token_span().hi))
+          .unwrap_or(BytePos(0));
+        mk_sp(pos, pos)
+      }
+    }
+  }
+}
+
+impl<'a, T> StreamSlice for Range<TokenStream<'a, T>>
+{
+  type Output = &'a [T];
+  fn stream_slice(&self) -> Self::Output {
+    &self.start.tokens[self.start.offset..self.end.offset]
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[derive(Clone, Debug, PartialEq)]
+  enum Token {
+    Number(u32),
+    Plus
+  }
+
+  #[test]
+  fn test_token_stream() {
+    let tokens = vec![Token::Number(1), Token::Plus, Token::Number(2)];
+    let mut stream = TokenStream::new(&tokens);
+    assert_eq!(stream.code_snippet(2), "Number(1) Plus");
+    let start = stream.clone();
+    assert_eq!(stream.next(), Some(Token::Number(1)));
+    assert!(start < stream);
+    assert_eq!(stream.next(), Some(Token::Plus));
+    assert_eq!(stream.next(), Some(Token::Number(2)));
+    assert_eq!(stream.next(), None);
+    assert!(!stream.has_next());
+    assert_eq!(stream.code_snippet(2), "<end-of-file>");
+  }
+}
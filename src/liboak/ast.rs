@@ -32,11 +32,12 @@ pub use partial::Partial;
 pub use middle::typing::ast::IType;
 pub use middle::typing::ast::Type;
 
-use middle::analysis::ast::GrammarAttributes;
+use middle::analysis::ast::{GrammarAttributes, Lint, LintLevel};
 
 use std::collections::HashMap;
 use std::default::Default;
 use std::ops::{Index, IndexMut};
+use std::ascii;
 
 pub trait ExprByIndex
 {
@@ -51,6 +52,8 @@ pub struct Grammar<'a, 'b:'a, ExprInfo>
   pub exprs: Vec<Expression>,
   pub exprs_info: Vec<ExprInfo>,
   pub stream_alias: RItem,
+  /// Generic parameters declared at the start of the grammar (e.g. `<T: FromStr>`), added to the generics of the generated parsers whose types need them.
+  pub generics: rust::Generics,
   pub rust_functions: HashMap<Ident, RItem>,
   pub rust_items: Vec<RItem>,
   pub attributes: GrammarAttributes
@@ -68,6 +71,7 @@ impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
       exprs: exprs,
       exprs_info: exprs_info,
       stream_alias: quote_item!(cx, pub type Stream<'a> = StrStream<'a>;).unwrap(),
+      generics: rust::Generics::default(),
       rust_functions: HashMap::new(),
       rust_items: vec![],
       attributes: GrammarAttributes::default()
@@ -105,6 +109,111 @@ impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
     self.cx.span_err(span, msg.as_str());
   }
 
+  /// Reports `lint`, raised in `rule`, with the level set by the attributes. The first message is rendered as a warning, or as an error if the lint is denied, and the other ones as notes. Returns `true` if the lint is denied: the analysis must then stop the compilation.
+  pub fn lint(&self, lint: Lint, rule: Option<Ident>, messages: Vec<(Span, String)>) -> bool {
+    self.lint_with_suggestion(lint, rule, messages, None)
+  }
+
+  /// Same as `lint` but proposes to replace the code at the span of `suggestion` by its replacement, in a form that tools can apply automatically. The suggestion is given as `(span, message, replacement)`.
+  pub fn lint_with_suggestion(&self, lint: Lint, rule: Option<Ident>, messages: Vec<(Span, String)>,
+    suggestion: Option<(Span, String, String)>) -> bool
+  {
+    assert!(messages.len() > 0, "`messages` must at least contain one element.");
+    let level = self.attributes.lint_level(lint, rule);
+    let mut messages_iter = messages.into_iter();
+    let (span, msg) = messages_iter.next().unwrap();
+    let mut db = match level {
+      LintLevel::Allow => return false,
+      LintLevel::Warn => self.cx.struct_span_warn(span, msg.as_str()),
+      LintLevel::Deny => self.cx.struct_span_err(span, msg.as_str())
+    };
+    for (span, msg) in messages_iter {
+      db.span_note(span, msg.as_str());
+    }
+    if let Some((span, msg, replacement)) = suggestion {
+      db.span_suggestion(span, msg.as_str(), replacement);
+    }
+    let level_name = if level == LintLevel::Deny { "deny" } else { "warn" };
+    let origin =
+      if self.attributes.rule_lint_level(lint, rule).is_some() {
+        format!("`#[{}({})]` is set on this rule.", level_name, lint.name())
+      }
+      else if self.attributes.grammar_lint_level(lint).is_some() {
+        format!("`#![{}({})]` is set on this grammar.", level_name, lint.name())
+      }
+      else {
+        format!("`#[{}({})]` on by default.", level_name, lint.name())
+      };
+    db.note(origin.as_str());
+    db.emit();
+    level == LintLevel::Deny
+  }
+
+  /// The source code covered by `span`, if it is available.
+  pub fn snippet(&self, span: Span) -> Option<String> {
+    self.cx.codemap().span_to_snippet(span).ok()
+  }
+
+  /// `true` if the expressions `expr1` and `expr2` are syntactically equal, regardless of their spans.
+  pub fn expr_eq(&self, expr1: usize, expr2: usize) -> bool {
+    use self::Expression::*;
+    let all_eq = |exprs1: &Vec<usize>, exprs2: &Vec<usize>| exprs1.len() == exprs2.len()
+      && exprs1.iter().zip(exprs2.iter()).all(|(&e1, &e2)| self.expr_eq(e1, e2));
+    match (&self.exprs[expr1], &self.exprs[expr2]) {
+      (&StrLiteral(ref lit1), &StrLiteral(ref lit2)) => lit1 == lit2,
+      (&ByteLiteral(ref bytes1), &ByteLiteral(ref bytes2)) => bytes1 == bytes2,
+      (&TokenPattern(ref pat1), &TokenPattern(ref pat2)) =>
+        rust::pat_to_string(pat1) == rust::pat_to_string(pat2),
+      (&AnySingleChar, &AnySingleChar) => true,
+      (&CharacterClass(ref class1), &CharacterClass(ref class2)) => class1.to_string() == class2.to_string(),
+      (&NonTerminalSymbol(rule1), &NonTerminalSymbol(rule2)) => rule1 == rule2,
+      (&Indentation(kind1), &Indentation(kind2)) => kind1 == kind2,
+      (&Sequence(ref exprs1), &Sequence(ref exprs2))
+    | (&Choice(ref exprs1), &Choice(ref exprs2)) => all_eq(exprs1, exprs2),
+      (&ZeroOrMore(e1), &ZeroOrMore(e2))
+    | (&OneOrMore(e1), &OneOrMore(e2))
+    | (&ZeroOrOne(e1), &ZeroOrOne(e2))
+    | (&NotPredicate(e1), &NotPredicate(e2))
+    | (&AndPredicate(e1), &AndPredicate(e2))
+    | (&SpannedExpr(e1), &SpannedExpr(e2))
+    | (&Capture(e1), &Capture(e2)) => self.expr_eq(e1, e2),
+      (&CountedRepeat(e1, count1), &CountedRepeat(e2, count2)) => count1 == count2 && self.expr_eq(e1, e2),
+      (&SemanticAction(e1, action1), &SemanticAction(e2, action2)) => action1 == action2 && self.expr_eq(e1, e2),
+      (&StructAction(e1, name1, ref fields1), &StructAction(e2, name2, ref fields2)) =>
+        name1 == name2 && fields1 == fields2 && self.expr_eq(e1, e2),
+      (&TypeAscription(e1, ref ty1), &TypeAscription(e2, ref ty2)) => {
+        let ty_eq = match (ty1, ty2) {
+          (&IType::Regular(Type::Action(rust::FunctionRetTy::Ty(ref ty1))),
+           &IType::Regular(Type::Action(rust::FunctionRetTy::Ty(ref ty2)))) =>
+            rust::ty_to_string(ty1) == rust::ty_to_string(ty2),
+          _ => ty1 == ty2
+        };
+        ty_eq && self.expr_eq(e1, e2)
+      }
+      (&Binding(label1, e1), &Binding(label2, e2)) => label1 == label2 && self.expr_eq(e1, e2),
+      (&AstNode(e1, ty1, variant1), &AstNode(e2, ty2, variant2)) =>
+        ty1 == ty2 && variant1 == variant2 && self.expr_eq(e1, e2),
+      (&Precedence(atom1, ref levels1), &Precedence(atom2, ref levels2)) =>
+        levels1.len() == levels2.len()
+        && levels1.iter().zip(levels2.iter()).all(|(l1, l2)|
+          l1.assoc == l2.assoc && l1.action == l2.action && self.expr_eq(l1.op, l2.op))
+        && self.expr_eq(atom1, atom2),
+      _ => false
+    }
+  }
+
+  /// The rule whose expression contains `expr_idx`, without going through non-terminal symbols.
+  pub fn rule_of_expr(&self, expr_idx: usize) -> Option<Ident> {
+    self.rules.iter()
+      .find(|rule| self.contains_expr(rule.expr_idx, expr_idx))
+      .map(|rule| rule.ident())
+  }
+
+  fn contains_expr(&self, parent: usize, expr_idx: usize) -> bool {
+    parent == expr_idx ||
+    self.exprs[parent].children().into_iter().any(|child| self.contains_expr(child, expr_idx))
+  }
+
   pub fn span_note(&self, span: Span, msg: String) {
     self.cx.parse_sess.span_diagnostic
       .span_note_without_error(span, msg.as_str());
@@ -121,6 +230,45 @@ impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
     self.find_rule_by_ident(id).expr_idx
   }
 
+  /// The labels bound by `expr_idx` if it is a binding or a sequence containing bindings, such as `name:identifier "=" value:expr`.
+  pub fn bound_labels(&self, expr_idx: usize) -> Vec<Ident> {
+    let label_of = |idx: usize| match self.exprs[idx] {
+      Expression::Binding(label, _) => Some(label),
+      _ => None
+    };
+    match self.exprs[expr_idx] {
+      Expression::Sequence(ref seq) => seq.iter().filter_map(|&idx| label_of(idx)).collect(),
+      _ => label_of(expr_idx).into_iter().collect()
+    }
+  }
+
+  /// The parameter names of the function `action`, `None` if a parameter is not a simple identifier.
+  pub fn action_params(&self, action: Ident) -> Vec<Option<Ident>> {
+    match self.rust_functions[&action].node {
+      rust::ItemKind::Fn(ref decl, ..) => {
+        decl.inputs.iter()
+          .map(|arg| match arg.pat.node {
+            rust::PatKind::Ident(_, ref ident, None) => Some(ident.node),
+            _ => None
+          })
+          .collect()
+      }
+      _ => vec![]
+    }
+  }
+
+  /// If `expr_idx` binds labels, the action `action` is called by name: its arguments are the labels with the same names as its parameters.
+  pub fn named_action_args(&self, expr_idx: usize, action: Ident) -> Option<Vec<Ident>> {
+    if self.bound_labels(expr_idx).is_empty() {
+      None
+    }
+    else {
+      Some(self.action_params(action).into_iter()
+        .filter_map(|param| param)
+        .collect())
+    }
+  }
+
   pub fn stream_generics(&self) -> rust::Generics {
     match &self.stream_alias.node {
       // The first arg is the type on the right of the type alias declaration.
@@ -167,9 +315,91 @@ impl<'a, 'b, ExprInfo> Grammar<'a, 'b, ExprInfo>
     } else { unreachable!() }
   }
 
+  /// The type of the items produced by the stream when it is known from the stream alias: `char` for `StrStream` and `FileMapStream`, `u8` for `ByteStream` and `T` for `TokenStream<'a, T>` if `T` is not a parameter of the alias.
+  pub fn stream_item_type(&self) -> Option<RTy> {
+    let segment = self.stream_segment()?;
+    match &*segment.identifier.name.as_str() {
+      "StrStream" | "FileMapStream" => Some(quote_ty!(self.cx, char)),
+      "ByteStream" => Some(quote_ty!(self.cx, u8)),
+      "TokenStream" => {
+        let token = match segment.parameters.as_ref().map(|params| (**params).clone()) {
+          Some(rust::PathParameters::AngleBracketed(data)) => data.types.last()?.clone(),
+          _ => return None
+        };
+        if self.depends_on_stream(&token) { None } else { Some(token) }
+      }
+      _ => None
+    }
+  }
+
+  /// The type of the atoms such as `.` or `["a-z"]`, it is `<Stream<'a> as Iterator>::Item` if the type of the items of the stream is not known.
+  pub fn atom_type(&self) -> RTy {
+    self.stream_item_type().unwrap_or_else(|| {
+      let stream_ty = self.stream_type();
+      quote_ty!(self.cx, <$stream_ty as Iterator>::Item)
+    })
+  }
+
+  /// The last segment of the type aliased by `Stream`, such as `StrStream<'a>`.
+  fn stream_segment(&self) -> Option<rust::PathSegment> {
+    match &self.stream_alias.node {
+      &rust::ItemKind::Ty(ref ty, _) => match ty.node {
+        rust::TyKind::Path(None, ref path) => path.segments.last().cloned(),
+        _ => None
+      },
+      _ => unreachable!()
+    }
+  }
+
+  /// The type of the spans, it is `oak_runtime::Span` for the streams of the runtime.
   pub fn span_type(&self) -> RTy {
+    let runtime_stream = self.stream_segment().map_or(false, |segment|
+      ["StrStream", "ByteStream", "FileMapStream", "TokenStream"]
+        .contains(&&*segment.identifier.name.as_str()));
+    if runtime_stream {
+      quote_ty!(self.cx, oak_runtime::Span)
+    }
+    else {
+      let stream_ty = self.stream_type();
+      quote_ty!(self.cx, <Range<$stream_ty> as StreamSpan>::Output)
+    }
+  }
+
+  /// The item clashing with the type `name` generated for a rule by `#![derive_ast]` or `#![auto_box]`: an item of the grammar, a semantic action (a tuple structure is also a function) or a name of the prelude or of the runtime used by the generated code, which would be shadowed.
+  pub fn generated_type_clash(&self, name: Ident) -> Option<String> {
+    static RESERVED: [&'static str; 21] = ["Option", "Some", "None", "Result", "Ok", "Err", "Vec",
+      "String", "Box", "Default", "Extend", "Iterator", "Range", "Span", "StrStream", "CharStream",
+      "HasNext", "ConsumePrefix", "Stream", "StreamSpan", "StreamSlice"];
+    if let Some(item) = self.rust_items.iter().find(|item| item.ident == name) {
+      Some(format!("the item `{}` declared in the grammar", item.ident))
+    }
+    else if self.rust_functions.contains_key(&name) {
+      Some(format!("the function `{}` declared in the grammar", name))
+    }
+    else if RESERVED.contains(&&*name.name.as_str()) {
+      Some(format!("`{}` used by the generated code", name))
+    }
+    else {
+      None
+    }
+  }
+
+  /// `true` if `ty` refers to a lifetime or a type parameter of the stream alias, such as `&'a str` for `type Stream<'a> = StrStream<'a>`.
+  pub fn depends_on_stream(&self, ty: &RTy) -> bool {
+    // The parameters of the alias are printed in `Stream<'a, T>`.
+    let stream_ty = rust::ty_to_string(&self.stream_type());
+    let is_param = |word: &str| !word.is_empty() && word != "Stream" && stream_ty
+      .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\''))
+      .any(|param| param == word);
+    rust::ty_to_string(ty)
+      .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\''))
+      .any(is_param)
+  }
+
+  /// The type of the input consumed by a capture `$e`, for example `&'a str` for `StrStream<'a>`.
+  pub fn slice_type(&self) -> RTy {
     let stream_ty = self.stream_type();
-    quote_ty!(self.cx, <Range<$stream_ty> as StreamSpan>::Output)
+    quote_ty!(self.cx, <Range<$stream_ty> as StreamSlice>::Output)
   }
 }
 
@@ -239,6 +469,8 @@ impl ItemSpan for Rule
 pub enum Expression
 {
   StrLiteral(String), // "match me"
+  ByteLiteral(Vec<u8>), // b"match me"
+  TokenPattern(RPat), // Token::Ident(_)
   AnySingleChar, // .
   CharacterClass(CharacterClassExpr), // [0-9]
   NonTerminalSymbol(Ident), // a_rule
@@ -247,24 +479,119 @@ pub enum Expression
   ZeroOrMore(usize), // expr*
   OneOrMore(usize), // expr+
   ZeroOrOne(usize), // expr?
+  CountedRepeat(usize, Ident), // expr{label}
   NotPredicate(usize), // !expr
   AndPredicate(usize), // &expr
   SemanticAction(usize, Ident), // expr > function
+  StructAction(usize, Ident, Vec<Ident>), // expr > Name { field, ... }
   TypeAscription(usize, IType), // expr -> () or expr -> (^)
   SpannedExpr(usize), // .. expr
+  Binding(Ident, usize), // label:expr
+  Capture(usize), // $expr
+  AstNode(usize, Ident, Option<Ident>), // generated by `#![derive_ast]`: builds `Type(..)` or `Type::Variant(..)` from the values of expr
+  Precedence(usize, Vec<PrecedenceLevel>), // precedence atom { left op > action, ... }
+  Indentation(IndentKind), // indent, dedent or samedent
+}
+
+impl Expression
+{
+  /// The sub-expressions of this expression, a non-terminal symbol has none.
+  pub fn children(&self) -> Vec<usize> {
+    use self::Expression::*;
+    match self {
+        &StrLiteral(_) | &ByteLiteral(_) | &TokenPattern(_) | &AnySingleChar
+      | &CharacterClass(_) | &NonTerminalSymbol(_) | &Indentation(_) => vec![],
+        &Sequence(ref children)
+      | &Choice(ref children) => children.clone(),
+        &ZeroOrMore(child) | &OneOrMore(child) | &ZeroOrOne(child)
+      | &CountedRepeat(child, _) | &NotPredicate(child) | &AndPredicate(child)
+      | &SemanticAction(child, _) | &StructAction(child, _, _) | &TypeAscription(child, _)
+      | &SpannedExpr(child) | &Binding(_, child) | &Capture(child) | &AstNode(child, _, _) => vec![child],
+      &Precedence(atom, ref levels) => {
+        let mut children: Vec<_> = levels.iter().map(|level| level.op).collect();
+        children.push(atom);
+        children
+      }
+    }
+  }
+}
+
+/// An operator level of a precedence expression. Levels are listed from the loosest to the tightest binding, and `action` is called with the operands and the value of `op` to build each node.
+#[derive(Clone, Debug)]
+pub struct PrecedenceLevel
+{
+  pub assoc: Associativity,
+  pub op: usize,
+  pub action: Ident
+}
+
+impl PrecedenceLevel
+{
+  pub fn new(assoc: Associativity, op: usize, action: Ident) -> PrecedenceLevel {
+    PrecedenceLevel {
+      assoc: assoc,
+      op: op,
+      action: action
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Associativity
+{
+  Left, // left op > action
+  Right, // right op > action
+  Prefix, // prefix op > action
+  Postfix // postfix op > action
+}
+
+/// Indentation primitives, they read and update the indentation stack of the parsing state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndentKind
+{
+  Indent, // indent: opens a block deeper than the current one
+  Dedent, // dedent: closes the current block
+  Samedent // samedent: continues the current block
+}
+
+impl IndentKind
+{
+  pub fn from_ident(ident: Ident) -> Option<IndentKind> {
+    match &*ident.name.as_str() {
+      "indent" => Some(IndentKind::Indent),
+      "dedent" => Some(IndentKind::Dedent),
+      "samedent" => Some(IndentKind::Samedent),
+      _ => None
+    }
+  }
+}
+
+/// The kind of atoms matched by a terminal: characters of a `"..."` literal or bytes of a `b"..."` literal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AtomKind
+{
+  Char,
+  Byte
 }
 
 #[derive(Clone, Debug)]
 pub struct CharacterClassExpr
 {
-  pub intervals: Vec<CharacterInterval>
+  pub intervals: Vec<CharacterInterval>,
+  /// For a byte class, the bounds of the intervals are characters in the range `0-255`.
+  pub kind: AtomKind
 }
 
 impl CharacterClassExpr
 {
   pub fn new(intervals: Vec<CharacterInterval>) -> CharacterClassExpr {
+    CharacterClassExpr::with_kind(intervals, AtomKind::Char)
+  }
+
+  pub fn with_kind(intervals: Vec<CharacterInterval>, kind: AtomKind) -> CharacterClassExpr {
     CharacterClassExpr {
-      intervals: intervals
+      intervals: intervals,
+      kind: kind
     }
   }
 }
@@ -272,14 +599,38 @@ impl CharacterClassExpr
 impl Display for CharacterClassExpr
 {
   fn fmt(&self, formatter: &mut Formatter) -> Result<(), Error> {
-    formatter.write_str("[\"")?;
-    for interval in &self.intervals {
-      interval.fmt(formatter)?;
+    match self.kind {
+      AtomKind::Char => {
+        formatter.write_str("[\"")?;
+        for interval in &self.intervals {
+          interval.fmt(formatter)?;
+        }
+      }
+      AtomKind::Byte => {
+        formatter.write_str("[b\"")?;
+        for interval in &self.intervals {
+          if interval.lo == interval.hi {
+            formatter.write_str(escape_byte(interval.lo as u8).as_str())?;
+          }
+          else {
+            formatter.write_fmt(format_args!("{}-{}",
+              escape_byte(interval.lo as u8), escape_byte(interval.hi as u8)))?;
+          }
+        }
+      }
     }
     formatter.write_str("\"]")
   }
 }
 
+pub fn escape_byte(byte: u8) -> String {
+  ascii::escape_default(byte).map(|c| c as char).collect()
+}
+
+pub fn escape_bytes(bytes: &Vec<u8>) -> String {
+  bytes.iter().map(|&byte| escape_byte(byte)).collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct CharacterInterval
 {
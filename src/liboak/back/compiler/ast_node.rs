@@ -0,0 +1,76 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+use back::compiler::ast_types::*;
+use rust::AstBuilder;
+
+/// Builds the node `Type(..)` or `Type::Variant(..)` generated by `#![derive_ast]` from the values of its expression.
+pub struct AstNodeCompiler
+{
+  expr_idx: usize,
+  ty: Ident,
+  variant: Option<Ident>,
+  boxing: Vec<Boxing>
+}
+
+impl AstNodeCompiler
+{
+  pub fn parser(grammar: &TGrammar, expr_idx: usize, ty: Ident, variant: Option<Ident>) -> AstNodeCompiler {
+    AstNodeCompiler {
+      expr_idx: expr_idx,
+      ty: ty,
+      variant: variant,
+      boxing: AstTypes::new(grammar).boxing(ty, expr_idx)
+    }
+  }
+}
+
+impl CompileExpr for AstNodeCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let cx = context.cx();
+    let result = context.next_free_var();
+    let scope = context.open_scope(self.expr_idx);
+    let span = context.expr_span(self.expr_idx);
+    let args: Vec<_> = context.free_variables().into_iter()
+      .zip(self.boxing.iter())
+      .map(|(var, boxing)| match *boxing {
+        Boxing::Unboxed => quote_expr!(cx, $var),
+        Boxing::Boxed => quote_expr!(cx, Box::new($var)),
+        Boxing::OptionBoxed => quote_expr!(cx, $var.map(Box::new))
+      })
+      .collect();
+    let path = match self.variant {
+      Some(variant) => cx.path(span, vec![self.ty, variant]),
+      None => cx.path_ident(span, self.ty)
+    };
+    let value =
+      if args.is_empty() { cx.expr_path(path) }
+      else { cx.expr_call(span, cx.expr_path(path), args) };
+    let expr = continuation
+      .map_success(|success, _|
+        quote_expr!(cx, {
+          let $result = $value;
+          $success
+        })
+      )
+      .compile_success(context, parser_compiler, self.expr_idx)
+      .unwrap_success();
+    context.close_scope(scope);
+    expr
+  }
+}
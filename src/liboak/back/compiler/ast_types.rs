@@ -0,0 +1,243 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates the types of the nodes built by `#![derive_ast]` from the inferred types of their expressions. A sequence rule gives a tuple structure and a choice rule gives an enumeration with one variant per branch.
+//!
+//! A field referring to a node type is boxed when this type can reach back the type being defined, otherwise the generated type would be infinite. For example, in `expr = "(" expr ")" / number`, the variant is `Expr::Alt1(Box<Expr>)`. The node types are also generated for the entry points of recursive types with `#![auto_box]`.
+
+use middle::typing::ast::*;
+use back::compiler::rtype::*;
+use rust;
+use rust::AstBuilder;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Boxing
+{
+  Unboxed,
+  Boxed,
+  OptionBoxed
+}
+
+pub struct AstTypes<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c TGrammar<'a, 'b>,
+  /// The type, variant and expression of every node.
+  nodes: Vec<(Ident, Option<Ident>, usize)>
+}
+
+impl<'a, 'b, 'c> AstTypes<'a, 'b, 'c>
+{
+  pub fn new(grammar: &'c TGrammar<'a, 'b>) -> AstTypes<'a, 'b, 'c> {
+    let nodes = grammar.exprs.iter()
+      .filter_map(|expr| match expr {
+        &AstNode(child, ty, variant) => Some((ty, variant, child)),
+        _ => None
+      })
+      .collect();
+    AstTypes {
+      grammar: grammar,
+      nodes: nodes
+    }
+  }
+
+  pub fn compile_items(&self) -> Vec<RItem> {
+    let mut types = vec![];
+    for &(ty, _, _) in &self.nodes {
+      if !types.contains(&ty) {
+        types.push(ty);
+      }
+    }
+    types.into_iter()
+      .map(|ty| self.compile_type(ty))
+      .collect()
+  }
+
+  /// The boxing of each value produced by the expression `child` of a node of type `ty`.
+  pub fn boxing(&self, ty: Ident, child: usize) -> Vec<Boxing> {
+    self.fields(child).into_iter()
+      .map(|field| self.field_boxing(ty, field))
+      .collect()
+  }
+
+  fn compile_type(&self, ty: Ident) -> RItem {
+    let cx = self.grammar.cx;
+    let nodes: Vec<_> = self.nodes.iter()
+      .filter(|node| node.0 == ty)
+      .cloned()
+      .collect();
+    let item = match nodes[0].1 {
+      None => {
+        let fields = self.compile_fields(ty, nodes[0].2, true);
+        if fields.is_empty() {
+          quote_item!(cx, pub struct $ty;)
+        }
+        else {
+          quote_item!(cx, pub struct $ty($fields);)
+        }
+      }
+      Some(_) => {
+        let variants: Vec<_> = nodes.into_iter()
+          .flat_map(|(_, variant, child)| {
+            let variant = variant.expect("A node of an enumeration must have a variant.");
+            let fields = self.compile_fields(ty, child, false);
+            if fields.is_empty() {
+              quote_tokens!(cx, $variant,)
+            }
+            else {
+              quote_tokens!(cx, $variant($fields),)
+            }
+          })
+          .collect();
+        quote_item!(cx, pub enum $ty { $variants })
+      }
+    };
+    let item = item.expect("Quote the type of an AST node.");
+    self.derive_traits(item)
+  }
+
+  /// Adds `#[derive(..)]` with the traits listed in `#![derive_ast(..)]` or `#![auto_box(..)]`.
+  fn derive_traits(&self, item: RItem) -> RItem {
+    let derives = &self.grammar.attributes.ast_derives;
+    if derives.is_empty() {
+      return item;
+    }
+    let cx = self.grammar.cx;
+    let sp = cx.call_site();
+    let traits = derives.iter()
+      .map(|derive| cx.meta_list_item_word(sp, derive.name))
+      .collect();
+    let derive = cx.attribute(sp, cx.meta_list(sp, rust::Symbol::intern("derive"), traits));
+    item.map(|mut item| {
+      item.attrs.push(derive);
+      item
+    })
+  }
+
+  #[allow(unused_imports)] // `quote_tokens` generates a warning.
+  fn compile_fields(&self, ty: Ident, child: usize, public: bool) -> Vec<rust::TokenTree> {
+    let cx = self.grammar.cx;
+    self.fields(child).into_iter()
+      .flat_map(|field| {
+        let field_ty = match self.field_boxing(ty, field) {
+          Boxing::Unboxed => self.field_type(field),
+          Boxing::Boxed => {
+            let field_ty = self.field_type(field);
+            quote_ty!(cx, Box<$field_ty>)
+          }
+          Boxing::OptionBoxed => {
+            let field_ty = match self.grammar[field].ty {
+              Type::Optional(child) => TypeCompiler::compile(self.grammar, child),
+              _ => unreachable!("BUG: Only optional fields can be boxed inside `Option`.")
+            };
+            quote_ty!(cx, Option<Box<$field_ty>>)
+          }
+        };
+        if public {
+          quote_tokens!(cx, pub $field_ty,)
+        }
+        else {
+          quote_tokens!(cx, $field_ty,)
+        }
+      })
+      .collect()
+  }
+
+  /// The expressions producing the values of `child`. A spanned expression stands for its span and is followed by the fields of its expression.
+  fn fields(&self, child: usize) -> Vec<usize> {
+    match self.grammar[child].ty {
+      Type::Unit => vec![],
+      Type::Tuple(ref indexes) => indexes.iter()
+        .flat_map(|idx| self.fields(*idx).into_iter())
+        .collect(),
+      Type::Spanned(expr) => {
+        let mut fields = vec![child];
+        fields.extend(self.fields(expr));
+        fields
+      }
+      _ => vec![child]
+    }
+  }
+
+  /// The generated types have no generic parameters, so a field borrowing from the stream, such as a capture `$e` of type `&'a str`, is reported.
+  fn field_type(&self, field: usize) -> RTy {
+    let field_ty = match self.grammar[field].ty {
+      Type::Spanned(_) => self.grammar.span_type(),
+      _ => TypeCompiler::compile(self.grammar, field)
+    };
+    if self.grammar.depends_on_stream(&field_ty) {
+      self.grammar.expr_err(field, format!(
+        "The value of this expression has type `{}` which depends on the parameters of the stream, \
+         it can not be a field of a type generated by `#![derive_ast]` or `#![auto_box]`.\n\
+         Help: convert the value with a semantic action, for example into a `String`.",
+        rust::ty_to_string(&*field_ty)));
+    }
+    field_ty
+  }
+
+  /// An optional node is boxed inside the `Option`, any other field containing a node type leading back to `ty` is boxed as a whole.
+  fn field_boxing(&self, ty: Ident, field: usize) -> Boxing {
+    let reaches = |node_ty| self.reaches(node_ty, ty, &mut vec![]);
+    match self.grammar[field].ty {
+      // The field of a spanned expression is its span, its values are the next fields.
+      Type::Spanned(_) => Boxing::Unboxed,
+      Type::Optional(child) if self.node_type(child).map_or(false, &reaches) => Boxing::OptionBoxed,
+      _ if self.contained_nodes(field).into_iter().any(&reaches) => Boxing::Boxed,
+      _ => Boxing::Unboxed
+    }
+  }
+
+  /// The node types contained in the value of `expr_idx` without indirection: `Vec` already provides one. The nodes can be nested in tuples, for example with `#![auto_box]`.
+  fn contained_nodes(&self, expr_idx: usize) -> Vec<Ident> {
+    match self.grammar[expr_idx].ty {
+      Type::Optional(child)
+    | Type::Spanned(child) => self.contained_nodes(child),
+      Type::Tuple(ref indexes) => indexes.iter()
+        .flat_map(|idx| self.contained_nodes(*idx).into_iter())
+        .collect(),
+      Type::Action(_) => self.node_type(expr_idx).into_iter().collect(),
+      _ => vec![]
+    }
+  }
+
+  fn node_type(&self, expr_idx: usize) -> Option<Ident> {
+    match self.grammar[expr_idx].ty {
+      Type::Action(rust::FunctionRetTy::Ty(ref ty)) => {
+        match ty.node {
+          rust::TyKind::Path(None, ref path) if path.segments.len() == 1 => {
+            let name = path.segments[0].identifier;
+            if self.nodes.iter().any(|node| node.0 == name) { Some(name) } else { None }
+          }
+          _ => None
+        }
+      }
+      _ => None
+    }
+  }
+
+  fn reaches(&self, from: Ident, to: Ident, visited: &mut Vec<Ident>) -> bool {
+    if from == to {
+      return true;
+    }
+    if visited.contains(&from) {
+      return false;
+    }
+    visited.push(from);
+    let children: Vec<_> = self.nodes.iter()
+      .filter(|node| node.0 == from)
+      .flat_map(|node| self.fields(node.2).into_iter())
+      .flat_map(|field| self.contained_nodes(field).into_iter())
+      .collect();
+    children.into_iter().any(|ty| self.reaches(ty, to, visited))
+  }
+}
@@ -0,0 +1,61 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+use back::compiler::value::*;
+
+/// `label:e` binds the value of `e` to the variable `label`, which is in scope of the next elements of the sequence. The binding is invisible so it is always compiled as a recognizer, but `e` is compiled as a parser to produce its value.
+pub struct BindingCompiler
+{
+  label: Ident,
+  expr_idx: usize
+}
+
+impl BindingCompiler
+{
+  pub fn recognizer(label: Ident, expr_idx: usize) -> BindingCompiler {
+    BindingCompiler {
+      label: label,
+      expr_idx: expr_idx
+    }
+  }
+
+  pub fn parser(label: Ident, expr_idx: usize) -> BindingCompiler {
+    BindingCompiler::recognizer(label, expr_idx)
+  }
+}
+
+impl CompileExpr for BindingCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let cx = context.cx();
+    let label = self.label;
+    let scope = context.open_scope(self.expr_idx);
+    let span = context.expr_span(self.expr_idx);
+    let value = tuple_value(cx, span, context.free_variables());
+    let expr = continuation
+      .map_success(|success, _|
+        quote_expr!(cx, {
+          let $label = $value;
+          $success
+        })
+      )
+      .compile_success(context, parser_compiler, self.expr_idx)
+      .unwrap_success();
+    context.close_scope(scope);
+    expr
+  }
+}
@@ -0,0 +1,59 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+use rust;
+use rust::{AstBuilder, LitKind};
+use std::rc::Rc;
+
+pub struct ByteLiteralCompiler
+{
+  literal: Vec<u8>
+}
+
+impl ByteLiteralCompiler
+{
+  pub fn recognizer(literal: Vec<u8>) -> ByteLiteralCompiler {
+    ByteLiteralCompiler {
+      literal: literal
+    }
+  }
+
+  pub fn parser(literal: Vec<u8>) -> ByteLiteralCompiler {
+    ByteLiteralCompiler::recognizer(literal)
+  }
+}
+
+impl CompileExpr for ByteLiteralCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let cx = context.cx();
+    let lit = cx.expr_lit(rust::DUMMY_SP, LitKind::ByteStr(Rc::new(self.literal.clone())));
+    let desc = format!("b\"{}\"", escape_bytes(&self.literal));
+    let desc_str = desc.as_str();
+    continuation
+      .map_success(|success, failure| quote_expr!(cx,
+        if state.consume_prefix(&$lit[..]) {
+          $success
+        }
+        else {
+          state.error($desc_str);
+          $failure
+        }
+      ))
+      .unwrap_success()
+  }
+}
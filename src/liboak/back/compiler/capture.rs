@@ -0,0 +1,56 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+
+/// `$e` produces the slice of the input consumed by `e`. The expression `e` is compiled as a recognizer since its value is not needed.
+pub struct CaptureCompiler
+{
+  expr_idx: usize
+}
+
+impl CaptureCompiler
+{
+  pub fn parser(expr_idx: usize) -> CaptureCompiler {
+    CaptureCompiler {
+      expr_idx: expr_idx
+    }
+  }
+}
+
+impl CompileExpr for CaptureCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let lo = context.next_mark_name();
+    let hi = context.next_mark_name();
+    let result = context.next_free_var();
+
+    let capture_expr = continuation
+      .map_success(|success, _| {
+        quote_expr!(context.cx(), {
+          let $hi = state.position();
+          let $result = Range { start: $lo, end: $hi }.stream_slice();
+          $success
+        })
+      })
+      .compile_success(context, recognizer_compiler, self.expr_idx)
+      .unwrap_success();
+    quote_expr!(context.cx(), {
+      let $lo = state.position();
+      $capture_expr
+    })
+  }
+}
@@ -50,7 +50,13 @@ impl CharacterClassCompiler
     char_interval: CharacterInterval, x: Ident) -> RExpr
   {
     let CharacterInterval{lo, hi} = char_interval;
-    quote_expr!(cx, ($x >= $lo && $x <= $hi))
+    match self.classes.kind {
+      AtomKind::Char => quote_expr!(cx, ($x >= $lo && $x <= $hi)),
+      AtomKind::Byte => {
+        let (lo, hi) = (lo as u8, hi as u8);
+        quote_expr!(cx, ($x >= $lo && $x <= $hi))
+      }
+    }
   }
 
   fn compile_condition(&self, cx: &ExtCtxt, x: Ident) -> RExpr {
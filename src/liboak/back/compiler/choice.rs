@@ -76,7 +76,7 @@ impl CompileExpr for ChoiceCompiler
       .fold(quote_expr!(cx, state), |accu, branch|
         quote_expr!(cx,
           if $branch_failed {
-            let mut state = state.restore_from_failure($mark.clone());
+            let mut state = state.restore_checkpoint_from_failure($mark.clone());
             let state = $branch;
             $accu
           }
@@ -84,7 +84,7 @@ impl CompileExpr for ChoiceCompiler
         ));
 
     quote_expr!(cx, {
-      let $mark = state.mark();
+      let $mark = state.checkpoint();
       let mut $branch_failed = true;
       let state = $first;
       $choice
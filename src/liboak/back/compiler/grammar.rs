@@ -15,6 +15,8 @@
 use middle::typing::ast::*;
 use back::code_printer::*;
 use back::compiler::rule::*;
+use back::compiler::ast_types::*;
+use back::context::Context;
 use rust;
 
 pub struct GrammarCompiler<'a, 'b: 'a>
@@ -86,14 +88,16 @@ impl<'a, 'b> GrammarCompiler<'a, 'b>
 
   fn compile_mod_content(&self) -> Vec<RItem> {
     let mut mod_content = self.compile_rules();
+    mod_content.extend(AstTypes::new(&self.grammar).compile_items().into_iter());
     mod_content.extend(self.grammar.rust_items.clone().into_iter());
     mod_content.extend(self.grammar.rust_functions.values().cloned());
     mod_content
   }
 
   fn compile_rules(&self) -> Vec<RItem> {
+    let grammar_params = Context::grammar_params(&self.grammar);
     self.grammar.rules.iter()
-      .flat_map(|&rule| RuleCompiler::compile(&self.grammar, rule).into_iter())
+      .flat_map(|&rule| RuleCompiler::compile(&self.grammar, &grammar_params, rule).into_iter())
       .collect()
   }
 
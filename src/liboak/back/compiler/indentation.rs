@@ -0,0 +1,58 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+
+pub struct IndentationCompiler
+{
+  kind: IndentKind
+}
+
+impl IndentationCompiler
+{
+  pub fn recognizer(kind: IndentKind) -> IndentationCompiler {
+    IndentationCompiler {
+      kind: kind
+    }
+  }
+
+  pub fn parser(kind: IndentKind) -> IndentationCompiler {
+    IndentationCompiler::recognizer(kind)
+  }
+}
+
+impl CompileExpr for IndentationCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let cx = context.cx();
+    let (primitive, expect) = match self.kind {
+      IndentKind::Indent => (quote_expr!(cx, state.indent()), "<indent>"),
+      IndentKind::Dedent => (quote_expr!(cx, state.dedent()), "<dedent>"),
+      IndentKind::Samedent => (quote_expr!(cx, state.samedent()), "<samedent>")
+    };
+    continuation
+      .map_success(|success, failure| quote_expr!(cx,
+        if $primitive {
+          $success
+        }
+        else {
+          state.error($expect);
+          $failure
+        }
+      ))
+      .unwrap_success()
+  }
+}
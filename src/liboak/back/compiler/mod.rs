@@ -19,6 +19,13 @@ pub mod value;
 mod grammar;
 mod rule;
 mod str_literal;
+mod byte_literal;
+mod token_pattern;
+mod binding;
+mod capture;
+mod ast_node;
+mod ast_types;
+mod type_ascription;
 mod sequence;
 mod choice;
 mod any_single_char;
@@ -29,10 +36,19 @@ mod character_class;
 mod non_terminal;
 mod semantic_action;
 mod spanned_expr;
+mod precedence;
+mod indentation;
 
+use rust;
 pub use back::compiler::grammar::*;
 pub use back::context::*;
 use back::compiler::str_literal::*;
+use back::compiler::byte_literal::*;
+use back::compiler::token_pattern::*;
+use back::compiler::binding::*;
+use back::compiler::capture::*;
+use back::compiler::ast_node::*;
+use back::compiler::type_ascription::*;
 use back::compiler::sequence::*;
 use back::compiler::choice::*;
 use back::compiler::any_single_char::*;
@@ -43,6 +59,8 @@ use back::compiler::character_class::*;
 use back::compiler::non_terminal::*;
 use back::compiler::semantic_action::*;
 use back::compiler::spanned_expr::*;
+use back::compiler::precedence::*;
+use back::compiler::indentation::*;
 
 pub enum CompilerKind
 {
@@ -64,6 +82,8 @@ pub fn parser_compiler(grammar: &TGrammar, idx: usize) -> Box<CompileExpr> {
   else {
     match grammar.expr_by_index(idx) {
       StrLiteral(lit) => Box::new(StrLiteralCompiler::parser(lit)),
+      ByteLiteral(lit) => Box::new(ByteLiteralCompiler::parser(lit)),
+      TokenPattern(pattern) => Box::new(TokenPatternCompiler::parser(pattern)),
       CharacterClass(classes) => Box::new(CharacterClassCompiler::parser(classes)),
       AnySingleChar => Box::new(AnySingleCharCompiler::parser()),
       Sequence(seq) => Box::new(SequenceCompiler::parser(seq)),
@@ -71,10 +91,22 @@ pub fn parser_compiler(grammar: &TGrammar, idx: usize) -> Box<CompileExpr> {
       ZeroOrOne(expr_idx) => Box::new(OptionalCompiler::parser(expr_idx)),
       ZeroOrMore(expr_idx) => Box::new(RepeatCompiler::parser(expr_idx, 0)),
       OneOrMore(expr_idx) => Box::new(RepeatCompiler::parser(expr_idx, 1)),
+      CountedRepeat(expr_idx, count) => Box::new(RepeatCompiler::counted_parser(expr_idx, count)),
       NonTerminalSymbol(id) => Box::new(NonTerminalCompiler::parser(id, idx)),
-      SemanticAction(expr_idx, id) => Box::new(SemanticActionCompiler::parser(expr_idx, id, idx)),
+      SemanticAction(expr_idx, id) => Box::new(SemanticActionCompiler::parser(expr_idx, id, idx,
+        grammar.named_action_args(expr_idx, id))),
+      StructAction(expr_idx, name, fields) => Box::new(StructActionCompiler::parser(expr_idx, name, fields, idx)),
+      TypeAscription(expr_idx, IType::Regular(Type::Collection(_, ty))) =>
+        Box::new(RepeatCompiler::collection_parser(grammar, expr_idx, ty)),
+      TypeAscription(expr_idx, IType::Regular(Type::Action(rust::FunctionRetTy::Ty(ty)))) =>
+        Box::new(TypeAscriptionCompiler::parser(expr_idx, ty)),
       TypeAscription(expr_idx, _) => parser_compiler(grammar, expr_idx),
       SpannedExpr(expr_idx) => Box::new(SpannedExprCompiler::parser(expr_idx)),
+      Binding(label, expr_idx) => Box::new(BindingCompiler::parser(label, expr_idx)),
+      Capture(expr_idx) => Box::new(CaptureCompiler::parser(expr_idx)),
+      AstNode(expr_idx, ty, variant) => Box::new(AstNodeCompiler::parser(grammar, expr_idx, ty, variant)),
+      Precedence(atom, levels) => Box::new(PrecedenceCompiler::parser(atom, levels, idx)),
+      Indentation(kind) => Box::new(IndentationCompiler::parser(kind)),
       NotPredicate(_)
     | AndPredicate(_) => unreachable!(
         "BUG: Syntactic predicate can not be compiled to parser (they do not generate data)."),
@@ -85,6 +117,8 @@ pub fn parser_compiler(grammar: &TGrammar, idx: usize) -> Box<CompileExpr> {
 pub fn recognizer_compiler(grammar: &TGrammar, idx: usize) -> Box<CompileExpr> {
   match grammar.expr_by_index(idx) {
     StrLiteral(lit) => Box::new(StrLiteralCompiler::recognizer(lit)),
+    ByteLiteral(lit) => Box::new(ByteLiteralCompiler::recognizer(lit)),
+    TokenPattern(pattern) => Box::new(TokenPatternCompiler::recognizer(pattern)),
     CharacterClass(classes) => Box::new(CharacterClassCompiler::recognizer(classes)),
     AnySingleChar => Box::new(AnySingleCharCompiler::recognizer()),
     Sequence(seq) => Box::new(SequenceCompiler::recognizer(seq)),
@@ -92,11 +126,18 @@ pub fn recognizer_compiler(grammar: &TGrammar, idx: usize) -> Box<CompileExpr> {
     ZeroOrOne(expr_idx) => Box::new(OptionalCompiler::recognizer(expr_idx)),
     ZeroOrMore(expr_idx) => Box::new(RepeatCompiler::recognizer(expr_idx, 0)),
     OneOrMore(expr_idx) => Box::new(RepeatCompiler::recognizer(expr_idx, 1)),
+    CountedRepeat(expr_idx, count) => Box::new(RepeatCompiler::counted_recognizer(expr_idx, count)),
     NotPredicate(expr_idx) => Box::new(SyntacticPredicateCompiler::recognizer(expr_idx, Kind::Not)),
     AndPredicate(expr_idx) => Box::new(SyntacticPredicateCompiler::recognizer(expr_idx, Kind::And)),
     NonTerminalSymbol(id) => Box::new(NonTerminalCompiler::recognizer(id)),
     SemanticAction(expr_idx, _) => recognizer_compiler(grammar, expr_idx),
+    StructAction(expr_idx, _, _) => recognizer_compiler(grammar, expr_idx),
     TypeAscription(expr_idx, _) => recognizer_compiler(grammar, expr_idx),
     SpannedExpr(expr_idx) => recognizer_compiler(grammar, expr_idx),
+    Binding(label, expr_idx) => Box::new(BindingCompiler::recognizer(label, expr_idx)),
+    Capture(expr_idx) => recognizer_compiler(grammar, expr_idx),
+    AstNode(expr_idx, _, _) => recognizer_compiler(grammar, expr_idx),
+    Precedence(atom, levels) => Box::new(PrecedenceCompiler::recognizer(atom, levels, idx)),
+    Indentation(kind) => Box::new(IndentationCompiler::recognizer(kind)),
   }
 }
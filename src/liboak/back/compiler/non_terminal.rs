@@ -14,7 +14,6 @@
 
 use back::compiler::*;
 use back::compiler::value::*;
-use back::name_factory::*;
 
 pub struct NonTerminalCompiler;
 
@@ -44,7 +43,7 @@ impl CompileExpr for NonTerminalRecognizerCompiler
   fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
     continuation: Continuation) -> RExpr
   {
-    let recognizer_fn = recognizer_name(context.cx(), self.id);
+    let recognizer_fn = context.recognizer_function_path(self.id);
     continuation
       .map_success(|success, failure| quote_expr!(context.cx(),
         {
@@ -74,7 +73,7 @@ impl CompileExpr for NonTerminalParserCompiler
     continuation: Continuation) -> RExpr
   {
     let cx = context.cx();
-    let parser_fn = parser_name(cx, self.id);
+    let parser_fn = context.parser_function_path(self.id);
     let cardinality = context.expr_cardinality(self.this_idx);
     let mut vars_names: Vec<_> = (0..cardinality)
       .map(|_| context.next_free_var())
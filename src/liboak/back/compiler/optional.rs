@@ -43,10 +43,10 @@ impl OptionalCompiler
     continuation
       .map_success(|success, _|
         quote_expr!(context.cx(), {
-          let $mark = state.mark();
+          let $mark = state.checkpoint();
           state = $body;
           if state.is_failed() {
-            state = state.restore_from_failure($mark);
+            state = state.restore_checkpoint_from_failure($mark);
           }
           $success
         })
@@ -0,0 +1,269 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A precedence expression is compiled into a loop recognizing `prefix* atom postfix* (infix prefix* atom postfix*)*`. The parser gives the operands and operators read to `oak_runtime::PrecedenceClimbing` which builds the nodes with the semantic actions according to the precedence and associativity of each level.
+
+use back::compiler::*;
+use back::compiler::value::*;
+use rust::AstBuilder;
+
+struct OperandBodies
+{
+  prefixes: Vec<RExpr>,
+  atom: RExpr,
+  postfixes: Vec<RExpr>,
+  infixes: Vec<RExpr>
+}
+
+pub struct PrecedenceCompiler
+{
+  atom: usize,
+  levels: Vec<PrecedenceLevel>,
+  this_idx: usize,
+  compiler_kind: CompilerKind
+}
+
+impl PrecedenceCompiler
+{
+  pub fn recognizer(atom: usize, levels: Vec<PrecedenceLevel>, this_idx: usize) -> PrecedenceCompiler {
+    PrecedenceCompiler {
+      atom: atom,
+      levels: levels,
+      this_idx: this_idx,
+      compiler_kind: CompilerKind::Recognizer
+    }
+  }
+
+  pub fn parser(atom: usize, levels: Vec<PrecedenceLevel>, this_idx: usize) -> PrecedenceCompiler {
+    PrecedenceCompiler {
+      atom: atom,
+      levels: levels,
+      this_idx: this_idx,
+      compiler_kind: CompilerKind::Parser
+    }
+  }
+
+  /// Levels (with their index) matching `is_kind`, in their declaration order.
+  fn levels_of<F>(&self, is_kind: F) -> Vec<(usize, PrecedenceLevel)> where
+   F: Fn(Associativity) -> bool
+  {
+    self.levels.iter().cloned()
+      .enumerate()
+      .filter(|&(_, ref level)| is_kind(level.assoc))
+      .collect()
+  }
+
+  fn prefix_levels(&self) -> Vec<(usize, PrecedenceLevel)> {
+    self.levels_of(|assoc| assoc == Associativity::Prefix)
+  }
+
+  fn postfix_levels(&self) -> Vec<(usize, PrecedenceLevel)> {
+    self.levels_of(|assoc| assoc == Associativity::Postfix)
+  }
+
+  fn infix_levels(&self) -> Vec<(usize, PrecedenceLevel)> {
+    self.levels_of(|assoc| assoc == Associativity::Left || assoc == Associativity::Right)
+  }
+
+  fn compile<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation, bodies: OperandBodies,
+    init: RStmt, finish: RStmt) -> RExpr
+  {
+    let cx = context.cx();
+    let mark = context.next_mark_name();
+    let operand_mark = context.next_mark_name();
+    let counter = context.next_counter_name();
+    let prefix_loop = PrecedenceCompiler::compile_unary_loop(cx, mark, bodies.prefixes);
+    let postfix_loop = PrecedenceCompiler::compile_unary_loop(cx, mark, bodies.postfixes);
+    let atom = bodies.atom;
+    // A successful infix operator continues with the next operand, otherwise we backtrack before the operator.
+    let infixes: Vec<_> = bodies.infixes.into_iter()
+      .map(|body| quote_stmt!(cx, {
+        state = $body;
+        if state.is_successful() {
+          continue;
+        }
+        state = state.restore_checkpoint_from_failure($operand_mark.clone());
+      }))
+      .collect();
+    continuation
+      .map_success(|success, failure| quote_expr!(cx, {
+        $init
+        let mut $operand_mark = state.checkpoint();
+        let mut $counter = 0;
+        loop {
+          $prefix_loop
+          state = $atom;
+          if state.is_failed() {
+            break;
+          }
+          $counter += 1;
+          $postfix_loop
+          $operand_mark = state.checkpoint();
+          $infixes
+          break;
+        }
+        if $counter == 0 {
+          $failure
+        }
+        else {
+          if state.is_failed() {
+            state = state.restore_checkpoint_from_failure($operand_mark);
+          }
+          $finish
+          $success
+        }
+      }))
+      .unwrap_success()
+  }
+
+  fn compile_unary_loop(cx: &ExtCtxt, mark: Ident, bodies: Vec<RExpr>) -> RStmt {
+    if bodies.is_empty() {
+      return None;
+    }
+    let attempts: Vec<_> = bodies.into_iter()
+      .map(|body| quote_stmt!(cx, {
+        state = $body;
+        if state.is_successful() {
+          continue;
+        }
+        state = state.restore_checkpoint_from_failure($mark.clone());
+      }))
+      .collect();
+    quote_stmt!(cx,
+      loop {
+        let $mark = state.checkpoint();
+        $attempts
+        break;
+      }
+    )
+  }
+
+  fn recognize_levels<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    levels: Vec<(usize, PrecedenceLevel)>) -> Vec<RExpr>
+  {
+    levels.into_iter()
+      .map(|(_, level)| context.compile_recognizer_expr(level.op))
+      .collect()
+  }
+
+  fn compile_recognizer<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let bodies = OperandBodies {
+      prefixes: self.recognize_levels(context, self.prefix_levels()),
+      atom: context.compile_recognizer_expr(self.atom),
+      postfixes: self.recognize_levels(context, self.postfix_levels()),
+      infixes: self.recognize_levels(context, self.infix_levels())
+    };
+    self.compile(context, continuation, bodies, None, None)
+  }
+
+  fn compile_parser<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let cx = context.cx();
+    let result = context.next_free_var();
+    let climbing = context.next_climbing_name();
+    let span = context.expr_span(self.this_idx);
+    let climbing_ty = quote_ty!(cx, oak_runtime::PrecedenceClimbing<_>);
+
+    let prefixes = self.prefix_levels().into_iter()
+      .map(|(i, level)| self.compile_operator(context, climbing, climbing_ty.clone(), i, level))
+      .collect();
+    let atom = context.accumulator_constructor(self.atom, climbing, climbing_ty.clone(),
+      |cx, climbing, vars| {
+        let operand = tuple_value(cx, span, vars);
+        quote_expr!(cx, {
+          $climbing.operand($operand);
+          state
+        })
+      });
+    let postfixes = self.postfix_levels().into_iter()
+      .map(|(i, level)| self.compile_operator(context, climbing, climbing_ty.clone(), i, level))
+      .collect();
+    let infixes = self.infix_levels().into_iter()
+      .map(|(i, level)| self.compile_operator(context, climbing, climbing_ty.clone(), i, level))
+      .collect();
+    let bodies = OperandBodies {
+      prefixes: prefixes,
+      atom: atom,
+      postfixes: postfixes,
+      infixes: infixes
+    };
+    self.compile(context, continuation, bodies,
+      quote_stmt!(cx, let mut $climbing = oak_runtime::PrecedenceClimbing::new();),
+      quote_stmt!(cx, let $result = $climbing.finish();))
+  }
+
+  /// Parses the operator of `level` and registers it with the function building the node. The values of the operator are given to the semantic action between (or next to) the operands.
+  fn compile_operator<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    climbing: Ident, climbing_ty: RTy, level_idx: usize, level: PrecedenceLevel) -> RExpr
+  {
+    let span = context.expr_span(self.this_idx);
+    let action = level.action;
+    context.accumulator_constructor(level.op, climbing, climbing_ty,
+      |cx, climbing, vars| {
+        let op_value = tuple_value(cx, span, vars.clone());
+        let op_pattern = tuple_pattern(cx, span, vars.clone());
+        let op_args: Vec<_> = vars.into_iter()
+          .map(|var| quote_expr!(cx, $var))
+          .collect();
+        let call = |before: Vec<RExpr>, after: Vec<RExpr>| {
+          let args = before.into_iter()
+            .chain(op_args.clone().into_iter())
+            .chain(after.into_iter())
+            .collect();
+          cx.expr_call_ident(span, action, args)
+        };
+        let operand = quote_expr!(cx, operand);
+        let lhs = quote_expr!(cx, lhs);
+        let rhs = quote_expr!(cx, rhs);
+        let register = match level.assoc {
+          Associativity::Prefix => {
+            let call = call(vec![], vec![operand]);
+            quote_expr!(cx, $climbing.prefix($level_idx, $op_value, |$op_pattern, operand| $call))
+          }
+          Associativity::Postfix => {
+            let call = call(vec![operand], vec![]);
+            quote_expr!(cx, $climbing.postfix($level_idx, $op_value, |operand, $op_pattern| $call))
+          }
+          Associativity::Left => {
+            let call = call(vec![lhs], vec![rhs]);
+            quote_expr!(cx, $climbing.infix_left($level_idx, $op_value, |lhs, $op_pattern, rhs| $call))
+          }
+          Associativity::Right => {
+            let call = call(vec![lhs], vec![rhs]);
+            quote_expr!(cx, $climbing.infix_right($level_idx, $op_value, |lhs, $op_pattern, rhs| $call))
+          }
+        };
+        quote_expr!(cx, {
+          $register;
+          state
+        })
+      })
+  }
+}
+
+impl CompileExpr for PrecedenceCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    match self.compiler_kind {
+      CompilerKind::Recognizer => self.compile_recognizer(context, continuation),
+      CompilerKind::Parser => self.compile_parser(context, continuation)
+    }
+  }
+}
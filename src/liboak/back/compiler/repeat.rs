@@ -18,6 +18,8 @@ pub struct RepeatCompiler
 {
   expr_idx: usize,
   cardinality_min: usize,
+  count: Option<Ident>,
+  collection: Option<RTy>,
   compiler_kind: CompilerKind
 }
 
@@ -27,6 +29,8 @@ impl RepeatCompiler
     RepeatCompiler {
       expr_idx: expr_idx,
       cardinality_min: cardinality_min,
+      count: None,
+      collection: None,
       compiler_kind: CompilerKind::Recognizer
     }
   }
@@ -35,13 +39,46 @@ impl RepeatCompiler
     RepeatCompiler {
       expr_idx: expr_idx,
       cardinality_min: cardinality_min,
+      count: None,
+      collection: None,
       compiler_kind: CompilerKind::Parser
     }
   }
 
+  pub fn counted_recognizer(expr_idx: usize, count: Ident) -> RepeatCompiler {
+    RepeatCompiler {
+      count: Some(count),
+      .. RepeatCompiler::recognizer(expr_idx, 0)
+    }
+  }
+
+  pub fn counted_parser(expr_idx: usize, count: Ident) -> RepeatCompiler {
+    RepeatCompiler {
+      count: Some(count),
+      .. RepeatCompiler::parser(expr_idx, 0)
+    }
+  }
+
+  /// The repetition `repeat_idx` ascribed with `e* -> ty` pushes its values directly into the collection `ty`.
+  pub fn collection_parser(grammar: &TGrammar, repeat_idx: usize, ty: RTy) -> RepeatCompiler {
+    let compiler = match grammar.expr_by_index(repeat_idx) {
+      ZeroOrMore(expr_idx) => RepeatCompiler::parser(expr_idx, 0),
+      OneOrMore(expr_idx) => RepeatCompiler::parser(expr_idx, 1),
+      CountedRepeat(expr_idx, count) => RepeatCompiler::counted_parser(expr_idx, count),
+      _ => unreachable!("BUG: Only repetitions can be collected into a Rust type.")
+    };
+    RepeatCompiler {
+      collection: Some(ty),
+      .. compiler
+    }
+  }
+
   fn compile<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
     continuation: Continuation, body: RExpr) -> RExpr
   {
+    if let Some(count) = self.count {
+      return self.compile_counted(context, continuation, body, count);
+    }
     let mark = context.next_mark_name();
     continuation.map_success(|success, failure|
       if self.cardinality_min > 0 {
@@ -49,13 +86,13 @@ impl RepeatCompiler
         let cardinality_min = self.cardinality_min;
         quote_expr!(context.cx(),
           {
-            let mut $mark = state.mark();
+            let mut $mark = state.checkpoint();
             let mut $counter = 0;
             loop {
               state = $body;
               if state.is_successful() {
                 $counter += 1;
-                $mark = state.mark();
+                $mark = state.checkpoint();
               }
               else {
                 break;
@@ -65,7 +102,7 @@ impl RepeatCompiler
               $failure
             }
             else {
-              let mut state = state.restore_from_failure($mark);
+              let mut state = state.restore_checkpoint_from_failure($mark);
               $success
             }
           }
@@ -74,17 +111,17 @@ impl RepeatCompiler
       else {
         quote_expr!(context.cx(),
           {
-            let mut $mark = state.mark();
+            let mut $mark = state.checkpoint();
             loop {
               state = $body;
               if state.is_successful() {
-                $mark = state.mark();
+                $mark = state.checkpoint();
               }
               else {
                 break;
               }
             }
-            let mut state = state.restore_from_failure($mark);
+            let mut state = state.restore_checkpoint_from_failure($mark);
             $success
           }
         )
@@ -93,6 +130,36 @@ impl RepeatCompiler
     .unwrap_success()
   }
 
+  /// `e{n}` matches `e` exactly `n` times where `n` is the value of a label bound earlier in the sequence.
+  fn compile_counted<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation, body: RExpr, count: Ident) -> RExpr
+  {
+    let counter = context.next_counter_name();
+    continuation.map_success(|success, failure|
+      quote_expr!(context.cx(),
+        {
+          let mut $counter = 0;
+          while $counter < ($count as usize) {
+            state = $body;
+            if state.is_successful() {
+              $counter += 1;
+            }
+            else {
+              break;
+            }
+          }
+          if $counter < ($count as usize) {
+            $failure
+          }
+          else {
+            $success
+          }
+        }
+      )
+    )
+    .unwrap_success()
+  }
+
   fn compile_recognizer<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
     continuation: Continuation) -> RExpr
   {
@@ -107,20 +174,43 @@ impl RepeatCompiler
     })
   }
 
+  fn extend_constructor(cx: &ExtCtxt, result_var: Ident, result_value: RExpr) -> RExpr {
+    quote_expr!(cx, {
+      $result_var.extend(::std::iter::once($result_value));
+      state
+    })
+  }
+
   fn compile_parser<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
     continuation: Continuation) -> RExpr
   {
-    let ty = quote_ty!(context.cx(), Vec<_>);
-    let (body, result_var) = context.value_constructor(
-      self.expr_idx,
-      ty,
-      RepeatCompiler::value_constructor
-    );
-    let repeat_expr = self.compile(context, continuation, body);
-    quote_expr!(context.cx(), {
-      let mut $result_var = vec![];
-      $repeat_expr
-    })
+    match self.collection.clone() {
+      None => {
+        let ty = quote_ty!(context.cx(), Vec<_>);
+        let (body, result_var) = context.value_constructor(
+          self.expr_idx,
+          ty,
+          RepeatCompiler::value_constructor
+        );
+        let repeat_expr = self.compile(context, continuation, body);
+        quote_expr!(context.cx(), {
+          let mut $result_var = vec![];
+          $repeat_expr
+        })
+      }
+      Some(ty) => {
+        let (body, result_var) = context.value_constructor(
+          self.expr_idx,
+          ty.clone(),
+          RepeatCompiler::extend_constructor
+        );
+        let repeat_expr = self.compile(context, continuation, body);
+        quote_expr!(context.cx(), {
+          let mut $result_var: $ty = ::std::default::Default::default();
+          $repeat_expr
+        })
+      }
+    }
   }
 }
 
@@ -40,11 +40,13 @@ impl<'a, 'b, 'c> TypeCompiler<'a, 'b, 'c>
   fn compile_type(&self, expr_idx: usize) -> RTy {
     match self.grammar[expr_idx].ty.clone() {
       Unit => self.unit_type(),
-      Atom => self.atom_type(),
+      Atom => self.grammar.atom_type(),
       List(expr_idx) => self.list_type(expr_idx),
+      Collection(_, ty) => ty,
       Optional(expr_idx) => self.optional_type(expr_idx),
       Action(rust_ty) => self.action_type(rust_ty),
-      Tuple(indexes) => self.tuple_type(expr_idx, indexes),
+      Tuple(_)
+    | Spanned(_) => self.tuple_type(expr_idx),
     }
   }
 
@@ -59,16 +61,31 @@ impl<'a, 'b, 'c> TypeCompiler<'a, 'b, 'c>
     quote_ty!(self.grammar.cx, ())
   }
 
-  fn tuple_type(&self, expr_idx: usize, indexes: Vec<usize>) -> RTy {
-    let tys: Vec<_> = indexes.into_iter()
-      .map(|idx| self.compile_type(idx))
-      .collect();
-    let span = self.grammar[expr_idx].span;
-    self.grammar.cx.ty(span, rust::TyKind::Tup(tys))
+  fn tuple_type(&self, expr_idx: usize) -> RTy {
+    let mut tys = self.flat_types(expr_idx);
+    if tys.len() == 1 {
+      tys.pop().unwrap()
+    }
+    else {
+      let span = self.grammar[expr_idx].span;
+      self.grammar.cx.ty(span, rust::TyKind::Tup(tys))
+    }
   }
 
-  fn atom_type(&self) -> RTy {
-    quote_ty!(self.grammar.cx, char)
+  /// The types of the values produced by `expr_idx`, the span of a spanned expression is followed by the values of its expression.
+  fn flat_types(&self, expr_idx: usize) -> Vec<RTy> {
+    match self.grammar[expr_idx].ty.clone() {
+      Unit => vec![],
+      Tuple(indexes) => indexes.into_iter()
+        .flat_map(|idx| self.flat_types(idx).into_iter())
+        .collect(),
+      Spanned(child) => {
+        let mut tys = vec![self.grammar.span_type()];
+        tys.extend(self.flat_types(child));
+        tys
+      }
+      _ => vec![self.compile_type(expr_idx)]
+    }
   }
 
   fn list_type(&self, expr_idx: usize) -> RTy {
@@ -18,28 +18,30 @@ use back::compiler::value::*;
 pub struct RuleCompiler<'a: 'c, 'b: 'a, 'c>
 {
   grammar: &'c TGrammar<'a, 'b>,
+  grammar_params: &'c RulesGrammarParams,
   rule: Rule
 }
 
 impl<'a, 'b, 'c> RuleCompiler<'a, 'b, 'c>
 {
-  pub fn compile(grammar: &'c TGrammar<'a, 'b>, rule: Rule) -> Vec<RItem> {
-    let compiler = RuleCompiler::new(grammar, rule);
+  pub fn compile(grammar: &'c TGrammar<'a, 'b>, grammar_params: &'c RulesGrammarParams, rule: Rule) -> Vec<RItem> {
+    let compiler = RuleCompiler::new(grammar, grammar_params, rule);
     vec![
       compiler.compile_recognizer(),
       compiler.compile_parser()
     ]
   }
 
-  fn new(grammar: &'c TGrammar<'a, 'b>, rule: Rule) -> Self {
+  fn new(grammar: &'c TGrammar<'a, 'b>, grammar_params: &'c RulesGrammarParams, rule: Rule) -> Self {
     RuleCompiler {
       grammar: grammar,
+      grammar_params: grammar_params,
       rule: rule
     }
   }
 
   fn compile_recognizer(&self) -> RItem {
-    let mut context = Context::new(self.grammar);
+    let mut context = Context::new(self.grammar, self.grammar_params);
     let success = quote_expr!(self.cx(), state.success(()));
     let failure = quote_expr!(self.cx(), state.failure());
 
@@ -50,7 +52,7 @@ impl<'a, 'b, 'c> RuleCompiler<'a, 'b, 'c>
   }
 
   fn compile_parser(&self) -> RItem {
-    let mut context = Context::new(self.grammar);
+    let mut context = Context::new(self.grammar, self.grammar_params);
     if self.parser_equals_recognizer() {
       context.into_parser_alias(self.rule)
     }
@@ -19,16 +19,21 @@ pub struct SemanticActionCompiler
 {
   expr_idx: usize,
   action: Ident,
-  this_idx: usize
+  this_idx: usize,
+  named_args: Option<Vec<Ident>>
 }
 
 impl SemanticActionCompiler
 {
-  pub fn parser(expr_idx: usize, action: Ident, this_idx: usize) -> SemanticActionCompiler {
+  /// `named_args` are the labels passed to the action if it is called by name.
+  pub fn parser(expr_idx: usize, action: Ident, this_idx: usize,
+    named_args: Option<Vec<Ident>>) -> SemanticActionCompiler
+  {
     SemanticActionCompiler {
       expr_idx: expr_idx,
       action: action,
-      this_idx: this_idx
+      this_idx: this_idx,
+      named_args: named_args
     }
   }
 }
@@ -42,7 +47,9 @@ impl CompileExpr for SemanticActionCompiler
     let result = context.next_free_var();
     let scope = context.open_scope(self.expr_idx);
     let span = context.expr_span(self.this_idx);
-    let args: Vec<_> = context.free_variables().into_iter()
+    let args: Vec<_> = self.named_args.clone()
+      .unwrap_or_else(|| context.free_variables())
+      .into_iter()
       .map(|var| quote_expr!(cx, $var))
       .collect();
     let action_call = cx.expr_call_ident(span, self.action, args);
@@ -59,3 +66,51 @@ impl CompileExpr for SemanticActionCompiler
     expr
   }
 }
+
+/// `e > Name { field, ... }` builds the structure `Name` where each field is initialized with the label of the same name.
+pub struct StructActionCompiler
+{
+  expr_idx: usize,
+  name: Ident,
+  fields: Vec<Ident>,
+  this_idx: usize
+}
+
+impl StructActionCompiler
+{
+  pub fn parser(expr_idx: usize, name: Ident, fields: Vec<Ident>, this_idx: usize) -> StructActionCompiler {
+    StructActionCompiler {
+      expr_idx: expr_idx,
+      name: name,
+      fields: fields,
+      this_idx: this_idx
+    }
+  }
+}
+
+impl CompileExpr for StructActionCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let cx = context.cx();
+    let result = context.next_free_var();
+    let scope = context.open_scope(self.expr_idx);
+    let span = context.expr_span(self.this_idx);
+    let fields: Vec<_> = self.fields.iter()
+      .map(|&field| cx.field_imm(span, field, quote_expr!(cx, $field)))
+      .collect();
+    let value = cx.expr_struct_ident(span, self.name, fields);
+    let expr = continuation
+      .map_success(|success, _|
+        quote_expr!(cx, {
+          let $result = $value;
+          $success
+        })
+      )
+      .compile_success(context, parser_compiler, self.expr_idx)
+      .unwrap_success();
+    context.close_scope(scope);
+    expr
+  }
+}
@@ -42,10 +42,25 @@ impl CompileExpr for SequenceCompiler
   fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
     continuation: Continuation) -> RExpr
   {
-    self.seq.clone().into_iter()
+    // The labels bound by the previous elements are in scope of each element.
+    let outer_labels = context.labels_in_scope();
+    let mut labels = outer_labels.clone();
+    let mut labels_in_scope = vec![];
+    for &idx in &self.seq {
+      labels_in_scope.push(labels.clone());
+      if let Some(label) = context.expr_label(idx) {
+        labels.push(label);
+      }
+    }
+    let expr = self.seq.clone().into_iter()
+      .zip(labels_in_scope.into_iter())
       .rev()
-      .fold(continuation, |continuation, idx|
-        continuation.compile_success(context, self.compiler, idx))
-      .unwrap_success()
+      .fold(continuation, |continuation, (idx, labels)| {
+        context.set_labels_in_scope(labels);
+        continuation.compile_success(context, self.compiler, idx)
+      })
+      .unwrap_success();
+    context.set_labels_in_scope(outer_labels);
+    expr
   }
 }
@@ -40,7 +40,7 @@ impl CompileExpr for SpannedExprCompiler
     let spanned_expr = continuation
       .map_success(|success, _| {
         quote_expr!(context.cx(), {
-          let $hi_sp = state.mark();
+          let $hi_sp = state.position();
           let $result = Range { start: $lo_sp, end: $hi_sp }.stream_span();
           $success
         })
@@ -48,7 +48,7 @@ impl CompileExpr for SpannedExprCompiler
       .compile_success(context, parser_compiler, self.expr_idx)
       .unwrap_success();
     quote_expr!(context.cx(), {
-      let $lo_sp = state.mark();
+      let $lo_sp = state.position();
       $spanned_expr
     })
   }
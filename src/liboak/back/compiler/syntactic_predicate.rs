@@ -45,10 +45,10 @@ impl SyntacticPredicateCompiler
       .unwrap_success();
     quote_expr!(context.cx(),
       {
-        let $mark = state.mark();
+        let $mark = state.checkpoint();
         state = $expr;
         let is_success = state.is_successful();
-        state = state.restore($mark);
+        state = state.restore_checkpoint($mark);
         if is_success {
           $success_case
         }
@@ -0,0 +1,99 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A token pattern such as `Token::Ident(_)` is compiled to a `match` on the next token of the stream.
+
+use back::compiler::*;
+use rust;
+
+pub struct TokenPatternCompiler
+{
+  pattern: RPat,
+  compiler_kind: CompilerKind
+}
+
+impl TokenPatternCompiler
+{
+  pub fn recognizer(pattern: RPat) -> TokenPatternCompiler {
+    TokenPatternCompiler {
+      pattern: pattern,
+      compiler_kind: CompilerKind::Recognizer
+    }
+  }
+
+  pub fn parser(pattern: RPat) -> TokenPatternCompiler {
+    TokenPatternCompiler {
+      pattern: pattern,
+      compiler_kind: CompilerKind::Parser
+    }
+  }
+}
+
+impl CompileExpr for TokenPatternCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let cx = context.cx();
+    let pattern_desc = rust::pat_to_string(&self.pattern);
+    let pattern_desc_str = pattern_desc.as_str();
+    let pattern = self.pattern.clone();
+    let mark = context.next_mark_name();
+    let restore = quote_expr!(cx, {
+      state = state.restore($mark);
+      state.error($pattern_desc_str);
+    });
+    let continuation = match self.compiler_kind {
+      CompilerKind::Recognizer => {
+        continuation
+          .map_success(|success, failure| quote_expr!(cx, {
+            let $mark = state.mark();
+            match state.next() {
+              Some($pattern) => {
+                $success
+              }
+              _ => {
+                $restore
+                $failure
+              }
+            }
+          }))
+      }
+      // The parser keeps the whole token. It is matched against a clone of the token since `var @ pattern` is not allowed when the pattern has bindings.
+      CompilerKind::Parser => {
+        let var = context.next_free_var();
+        continuation
+          .map_success(|success, failure| quote_expr!(cx, {
+            let $mark = state.mark();
+            match state.next() {
+              Some($var) => {
+                if match $var.clone() { $pattern => true, _ => false } {
+                  $success
+                }
+                else {
+                  $restore
+                  $failure
+                }
+              }
+              _ => {
+                $restore
+                $failure
+              }
+            }
+          }))
+      }
+    };
+    continuation.unwrap_success()
+  }
+}
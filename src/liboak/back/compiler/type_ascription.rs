@@ -0,0 +1,58 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use back::compiler::*;
+use back::compiler::value::*;
+
+/// `e -> Ty` converts the value of `e` into `Ty` with `Into`, which is the identity when the inferred type of `e` is already `Ty`.
+pub struct TypeAscriptionCompiler
+{
+  expr_idx: usize,
+  ty: RTy
+}
+
+impl TypeAscriptionCompiler
+{
+  pub fn parser(expr_idx: usize, ty: RTy) -> TypeAscriptionCompiler {
+    TypeAscriptionCompiler {
+      expr_idx: expr_idx,
+      ty: ty
+    }
+  }
+}
+
+impl CompileExpr for TypeAscriptionCompiler
+{
+  fn compile_expr<'a, 'b, 'c>(&self, context: &mut Context<'a, 'b, 'c>,
+    continuation: Continuation) -> RExpr
+  {
+    let cx = context.cx();
+    let result = context.next_free_var();
+    let scope = context.open_scope(self.expr_idx);
+    let span = context.expr_span(self.expr_idx);
+    let value = tuple_value(cx, span, context.free_variables());
+    let ty = self.ty.clone();
+    let expr = continuation
+      .map_success(|success, _|
+        quote_expr!(cx, {
+          let $result: $ty = ::std::convert::Into::into($value);
+          $success
+        })
+      )
+      .compile_success(context, parser_compiler, self.expr_idx)
+      .unwrap_success();
+    context.close_scope(scope);
+    expr
+  }
+}
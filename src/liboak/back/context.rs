@@ -21,53 +21,65 @@ use back::compiler::{recognizer_compiler, parser_compiler};
 use back::compiler::value::*;
 use rust;
 use rust::AstBuilder;
+use middle::typing::instantiation::Instantiation;
+use std::collections::HashMap;
+
+/// The type parameters of the grammar needed by the parser of every rule, see `Context::grammar_params`.
+pub type RulesGrammarParams = HashMap<Ident, Vec<Ident>>;
 
 pub struct Context<'a: 'c, 'b: 'a, 'c>
 {
   grammar: &'c TGrammar<'a, 'b>,
+  grammar_params: &'c RulesGrammarParams,
   closures: Vec<RStmt>,
   name_factory: NameFactory,
   free_variables: Vec<Ident>,
   mut_ref_free_variables: Vec<(Ident, RTy)>,
-  num_combinators_compiled: usize
+  num_combinators_compiled: usize,
+  /// Labels (`label:e`) bound before the expression currently compiled.
+  labels: Vec<Ident>
 }
 
 impl<'a, 'b, 'c> Context<'a, 'b, 'c>
 {
-  pub fn new(grammar: &'c TGrammar<'a, 'b>) -> Self
+  pub fn new(grammar: &'c TGrammar<'a, 'b>, grammar_params: &'c RulesGrammarParams) -> Self
   {
     Context {
       grammar: grammar,
+      grammar_params: grammar_params,
       closures: vec![],
       name_factory: NameFactory::new(),
       free_variables: vec![],
       mut_ref_free_variables: vec![],
-      num_combinators_compiled: 0
+      num_combinators_compiled: 0,
+      labels: vec![]
     }
   }
 
   pub fn into_recognizer_function(self, body: RExpr, rule: Rule) -> RItem {
     let cx = self.cx();
     let recognizer_fn = recognizer_name(cx, rule.ident());
-    self.function(recognizer_fn, true, body, quote_ty!(cx, ()))
+    self.function(recognizer_fn, true, body, quote_ty!(cx, ()), vec![])
   }
 
   pub fn into_parser_alias(self, rule: Rule) -> RItem {
     let cx = self.cx();
-    let recognizer_fn = recognizer_name(cx, rule.ident());
+    let recognizer_fn = self.recognizer_function_path(rule.ident());
     let parser_fn = parser_name(cx, rule.ident());
     self.function(parser_fn, false,
       quote_expr!(cx, $recognizer_fn(state)),
-      quote_ty!(cx, ()))
+      quote_ty!(cx, ()), vec![])
   }
 
   pub fn into_parser_function(self, body: RExpr, rule: Rule) -> RItem {
     let parser_fn = parser_name(self.cx(), rule.ident());
     let ty = TypeCompiler::compile(self.grammar, rule.expr_idx);
-    self.function(parser_fn, true, body, ty)
+    let grammar_params = self.grammar_params_of(rule.ident());
+    self.function(parser_fn, true, body, ty, grammar_params)
   }
 
-  fn function(self, name: Ident, state_mut: bool, body: RExpr, ty: RTy) -> RItem {
+  /// The generated function has the generics of the stream and the type parameters `grammar_params` of the grammar, with the lifetimes of the grammar and the where clauses bounding these parameters.
+  fn function(self, name: Ident, state_mut: bool, body: RExpr, ty: RTy, grammar_params: Vec<Ident>) -> RItem {
     let cx = self.cx();
     let state_param = self.state_param(state_mut);
     let stream_ty = self.grammar.stream_type();
@@ -82,8 +94,23 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
     ).expect("Quotation of a generated function.");
     if let rust::ItemKind::Fn(a,b,c,d,mut generics,f) = fun.node.clone() {
       let stream_gen = self.grammar.stream_generics();
+      let grammar_gen = self.grammar.generics.clone();
+      let all_params = Instantiation::new(&grammar_gen);
+      let dropped: Vec<_> = grammar_type_params(&grammar_gen).into_iter()
+        .filter(|param| !grammar_params.contains(param))
+        .collect();
       generics.params = stream_gen.params;
+      generics.params.extend(grammar_gen.params.into_iter().filter(|param| match param {
+        &rust::GenericParam::Type(ref ty) => grammar_params.contains(&ty.ident),
+        _ => true
+      }));
       generics.where_clause = stream_gen.where_clause;
+      generics.where_clause.predicates.extend(grammar_gen.where_clause.predicates.into_iter()
+        .filter(|predicate| match predicate {
+          &rust::WherePredicate::BoundPredicate(ref bound) =>
+            !dropped.iter().any(|param| all_params.unbound_params(&bound.bounded_ty).contains(param)),
+          _ => true
+        }));
       let item = rust::Item {
         ident: fun.ident,
         attrs: fun.attrs.clone(),
@@ -97,6 +124,82 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
     } else { unreachable!() }
   }
 
+  /// The type parameters of the grammar needed by the parser of every rule: the ones occurring in its type and the ones needed by the parsers of the rules it calls. The parser of a rule of type `()` is an alias to its recognizer, which does not need them. It is computed once for the grammar and shared by the contexts of its rules.
+  pub fn grammar_params(grammar: &TGrammar) -> RulesGrammarParams {
+    let params = Instantiation::new(&grammar.generics);
+    let is_unit = |rule: Ident| grammar[grammar.expr_index_of_rule(rule)].ty == Type::Unit;
+    let mut needed: RulesGrammarParams = grammar.rules.iter()
+      .map(|r| {
+        let needed =
+          if !params.is_generic() || is_unit(r.ident()) { vec![] }
+          else { params.unbound_params(&TypeCompiler::compile(grammar, r.expr_idx)) };
+        (r.ident(), needed)
+      })
+      .collect();
+    if !params.is_generic() {
+      return needed;
+    }
+    let calls: Vec<_> = grammar.rules.iter()
+      .filter(|r| !is_unit(r.ident()))
+      .map(|r| (r.ident(), called_rules(grammar, r.expr_idx)))
+      .collect();
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for &(caller, ref callees) in &calls {
+        for callee in callees {
+          for param in needed[callee].clone() {
+            let needed_by_caller = needed.get_mut(&caller).unwrap();
+            if !needed_by_caller.contains(&param) {
+              needed_by_caller.push(param);
+              changed = true;
+            }
+          }
+        }
+      }
+    }
+    needed
+  }
+
+  /// The type parameters of the grammar needed by the parser of `rule`, in the order of their declaration.
+  fn grammar_params_of(&self, rule: Ident) -> Vec<Ident> {
+    let needed = &self.grammar_params[&rule];
+    grammar_type_params(&self.grammar.generics).into_iter()
+      .filter(|param| needed.contains(param))
+      .collect()
+  }
+
+  pub fn recognizer_function_path(&self, rule: Ident) -> RExpr {
+    let name = recognizer_name(self.cx(), rule);
+    self.function_path(name, vec![])
+  }
+
+  pub fn parser_function_path(&self, rule: Ident) -> RExpr {
+    let name = parser_name(self.cx(), rule);
+    let grammar_params = self.grammar_params_of(rule);
+    self.function_path(name, grammar_params)
+  }
+
+  /// The path calling the generated function `name`. The type parameters of the grammar can not be inferred from the arguments, so they are given explicitly, the stream type parameters are left to inference.
+  fn function_path(&self, name: Ident, grammar_params: Vec<Ident>) -> RExpr {
+    let cx = self.cx();
+    let grammar_params: Vec<_> = grammar_params.into_iter()
+      .map(|ident| quote_ty!(cx, $ident))
+      .collect();
+    let mut types: Vec<_> = self.grammar.stream_generics().params.iter()
+      .filter(|param| match param {
+        &&rust::GenericParam::Type(_) => true,
+        _ => false
+      })
+      .map(|_| cx.ty_infer(cx.call_site()))
+      .collect();
+    if grammar_params.is_empty() {
+      types.clear();
+    }
+    types.extend(grammar_params);
+    cx.expr_path(cx.path_all(cx.call_site(), false, vec![name], vec![], types, vec![]))
+  }
+
   #[allow(unused_imports)] // `quote_tokens` generates a warning.
   fn state_param(&self, state_mut: bool) -> RArg {
     let mut_kw = if state_mut {
@@ -139,27 +242,43 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
    F: FnOnce(&ExtCtxt, Ident, RExpr) -> RExpr,
   {
     let result_var = self.next_free_var();
+    let body = self.accumulator_constructor(expr_idx, result_var, value_ty,
+      |cx, result_var, vars| {
+        let result_value = tuple_value(cx, cx.call_site(), vars);
+        value_constructor(cx, result_var, result_value)
+      });
+    (body, result_var)
+  }
+
+  /// Compiles the parser of `expr_idx` such that, in case of success, `constructor` is called with the mutable variable `accu_var` and the variables holding the values of `expr_idx`.
+  pub fn accumulator_constructor<F>(&mut self,
+    expr_idx: usize,
+    accu_var: Ident,
+    accu_ty: RTy,
+    constructor: F) -> RExpr where
+   F: FnOnce(&ExtCtxt, Ident, Vec<Ident>) -> RExpr,
+  {
     let scope = self.open_scope(expr_idx);
-    self.push_mut_ref_fv(result_var, value_ty);
-    let span = self.cx().call_site();
-    let result_value = tuple_value(self.cx(), span, self.free_variables());
+    self.push_mut_ref_fv(accu_var, accu_ty);
+    let vars = self.free_variables();
     let body =
       Continuation::new(
-        value_constructor(self.cx(), result_var, result_value),
+        constructor(self.cx(), accu_var, vars),
         quote_expr!(self.cx(), state.failure())
       )
       .compile_success(self, parser_compiler, expr_idx)
       .unwrap_success();
     self.close_scope(scope);
-    (body, result_var)
+    body
   }
 
   pub fn do_not_duplicate_success(&self) -> bool {
     self.num_combinators_compiled > 0
   }
 
+  /// The success continuation is not moved into a closure when labels are in scope since the closure is declared before the labels are bound.
   pub fn success_as_closure(&mut self, continuation: Continuation) -> Continuation {
-    if self.do_not_duplicate_success() {
+    if self.do_not_duplicate_success() && self.labels.is_empty() {
       self.num_combinators_compiled = 0;
       let cx = self.cx();
       let closure_name = self.name_factory.next_closure_name(cx);
@@ -215,11 +334,32 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
     self.name_factory.next_counter_name(cx)
   }
 
+  pub fn next_climbing_name(&mut self) -> Ident {
+    let cx = self.cx();
+    self.name_factory.next_climbing_name(cx)
+  }
+
   pub fn next_branch_failed_name(&mut self) -> Ident {
     let cx = self.cx();
     self.name_factory.next_branch_failed_name(cx)
   }
 
+  pub fn labels_in_scope(&self) -> Vec<Ident> {
+    self.labels.clone()
+  }
+
+  pub fn set_labels_in_scope(&mut self, labels: Vec<Ident>) {
+    self.labels = labels;
+  }
+
+  /// The label of `expr_idx` if it is a binding `label:e`.
+  pub fn expr_label(&self, expr_idx: usize) -> Option<Ident> {
+    match self.grammar.expr_by_index(expr_idx) {
+      Expression::Binding(label, _) => Some(label),
+      _ => None
+    }
+  }
+
   pub fn next_free_var(&mut self) -> Ident {
     self.free_variables.pop().expect("Free variables are all bound.")
   }
@@ -244,7 +384,7 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c>
   }
 
   pub fn expr_cardinality(&self, expr_idx: usize) -> usize {
-    self.grammar[expr_idx].type_cardinality()
+    self.grammar.cardinality(expr_idx)
   }
 
   pub fn expr_span(&self, expr_idx: usize) -> Span {
@@ -303,3 +443,24 @@ impl Scope {
     }
   }
 }
+
+fn grammar_type_params(generics: &rust::Generics) -> Vec<Ident> {
+  generics.params.iter()
+    .filter_map(|param| match param {
+      &rust::GenericParam::Type(ref ty) => Some(ty.ident),
+      _ => None
+    })
+    .collect()
+}
+
+fn called_rules(grammar: &TGrammar, expr_idx: usize) -> Vec<Ident> {
+  let mut calls = vec![];
+  let mut to_visit = vec![expr_idx];
+  while let Some(e) = to_visit.pop() {
+    if let Expression::NonTerminalSymbol(rule) = grammar.exprs[e] {
+      calls.push(rule);
+    }
+    to_visit.extend(grammar.exprs[e].children());
+  }
+  calls
+}
@@ -29,7 +29,8 @@ pub struct NameFactory
   mark_uid: usize,
   branch_failed_uid: usize,
   counter_uid: usize,
-  closure_uid: usize
+  closure_uid: usize,
+  climbing_uid: usize
 }
 
 impl NameFactory
@@ -40,7 +41,8 @@ impl NameFactory
       mark_uid: 0,
       branch_failed_uid: 0,
       counter_uid: 0,
-      closure_uid: 0
+      closure_uid: 0,
+      climbing_uid: 0
     }
   }
 
@@ -64,6 +66,11 @@ impl NameFactory
     string_to_ident(cx, format!("counter{}", self.counter_uid))
   }
 
+  pub fn next_climbing_name(&mut self, cx: &ExtCtxt) -> Ident {
+    self.climbing_uid += 1;
+    string_to_ident(cx, format!("climbing{}", self.climbing_uid))
+  }
+
   pub fn fresh_vars(&mut self, cx: &ExtCtxt, cardinality: usize) -> Vec<Ident> {
     let prefix = self.next_var_prefix();
     (0..cardinality)
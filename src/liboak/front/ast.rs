@@ -14,15 +14,18 @@
 
 pub use rust::{Spanned, BytePos, NO_EXPANSION};
 pub use ast::*;
+use rust;
 
 pub struct FGrammar
 {
   pub name: Ident,
   pub rules: Vec<Rule>,
+  pub rules_attributes: Vec<(Ident, Vec<Attribute>)>,
   pub exprs: Vec<Expression>,
   pub exprs_info: Vec<FExpressionInfo>,
   pub rust_items: Vec<RItem>,
-  pub attributes: Vec<Attribute>
+  pub attributes: Vec<Attribute>,
+  pub generics: rust::Generics
 }
 
 impl FGrammar
@@ -31,10 +34,12 @@ impl FGrammar
     FGrammar {
       name: grammar_name,
       rules: vec![],
+      rules_attributes: vec![],
       exprs: vec![],
       exprs_info: vec![],
       rust_items: vec![],
-      attributes: vec![]
+      attributes: vec![],
+      generics: rust::Generics::default()
     }
   }
 
@@ -45,8 +50,11 @@ impl FGrammar
     expr_idx
   }
 
-  pub fn push_rule(&mut self, name: SpannedIdent, def: usize) {
+  pub fn push_rule(&mut self, name: SpannedIdent, def: usize, attrs: Vec<Attribute>) {
     self.rules.push(Rule::new(name, def));
+    if !attrs.is_empty() {
+      self.rules_attributes.push((name.node, attrs));
+    }
   }
 
   pub fn push_attr(&mut self, attr: Attribute) {
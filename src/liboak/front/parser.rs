@@ -45,6 +45,8 @@ impl<'a> Parser<'a>
   }
 
   pub fn parse_grammar(mut self) -> rust::PResult<'a, FGrammar> {
+    self.parse_inner_attributes()?;
+    self.parse_grammar_generics()?;
     self.parse_blocks()?;
     Ok(self.grammar)
   }
@@ -70,11 +72,37 @@ impl<'a> Parser<'a>
     Ok(())
   }
 
+  /// The generic parameters of the grammar, such as in `grammar! list { <T: FromStr> ... }`. They are written at the start of the body because a macro invocation can not have tokens between its name and its body.
+  fn parse_grammar_generics(&mut self) -> rust::PResult<'a, ()> {
+    if self.rp.token == rtok::Lt {
+      let mut generics = self.rp.parse_generics()?;
+      generics.where_clause = self.rp.parse_where_clause()?;
+      self.grammar.generics = generics;
+    }
+    Ok(())
+  }
+
   fn parse_rule(&mut self) -> rust::PResult<'a, ()> {
+    let attrs = self.rp.parse_outer_attributes()?;
     let name = self.parse_rule_decl()?;
+    let ty =
+      if self.rp.token == rtok::Colon {
+        self.bump();
+        let ty = self.rp.parse_ty()?;
+        self.check_placeholder(&ty, ident_to_string(name.node).as_str());
+        Some(ty)
+      }
+      else {
+        None
+      };
     self.rp.expect(&rtok::Eq)?;
-    let body = self.parse_rule_rhs(ident_to_string(name.node).as_str())?;
-    self.grammar.push_rule(name, body);
+    let lo = self.rp.span.lo();
+    let mut body = self.parse_rule_rhs(ident_to_string(name.node).as_str())?;
+    if let Some(ty) = ty {
+      let hi = self.rp.prev_span.hi();
+      body = self.alloc_expr(lo, hi, TypeAscription(body, IType::rust_type(ty)));
+    }
+    self.grammar.push_rule(name, body, attrs);
     Ok(())
   }
 
@@ -88,7 +116,13 @@ impl<'a> Parser<'a>
 
   fn parse_rule_decl(&mut self) -> rust::PResult<'a, rust::SpannedIdent> {
     let sp = self.rp.span;
-    Ok(respan(sp, self.rp.parse_ident()?))
+    let name = self.rp.parse_ident()?;
+    if IndentKind::from_ident(name).is_some() {
+      self.rp.span_err(sp, format!(
+        "`{}` is a reserved name for the indentation primitives, it can not be used as a rule name.",
+        name).as_str());
+    }
+    Ok(respan(sp, name))
   }
 
   fn parse_rule_rhs(&mut self, rule_name: &str) -> rust::PResult<'a, usize> {
@@ -120,11 +154,28 @@ impl<'a> Parser<'a>
     let token = self.rp.token.clone();
     let lo = self.rp.span.lo();
     match token {
-      rtok::Gt => {
+      // `e >@ f` passes the span of `e` as the first argument of `f`, it is a shortcut for `..e > f`.
+      rtok::Gt if self.rp.look_ahead(1, |t| *t == rtok::At) => {
+        self.bump();
         self.bump();
         let ident = self.rp.parse_ident()?;
         let hi = self.rp.span.hi();
-        Ok(self.alloc_expr(lo, hi, SemanticAction(expr, ident)))
+        let expr_sp = self.grammar.exprs_info[expr].span;
+        let spanned = self.alloc_expr(expr_sp.lo(), expr_sp.hi(), SpannedExpr(expr));
+        Ok(self.alloc_expr(lo, hi, SemanticAction(spanned, ident)))
+      },
+      rtok::Gt => {
+        self.bump();
+        let ident = self.rp.parse_ident()?;
+        if self.rp.token == rtok::OpenDelim(rust::DelimToken::Brace) {
+          let fields = self.parse_struct_fields()?;
+          let hi = self.rp.prev_span.hi();
+          Ok(self.alloc_expr(lo, hi, StructAction(expr, ident, fields)))
+        }
+        else {
+          let hi = self.rp.span.hi();
+          Ok(self.alloc_expr(lo, hi, SemanticAction(expr, ident)))
+        }
       },
       rtok::RArrow => {
         self.bump();
@@ -134,11 +185,52 @@ impl<'a> Parser<'a>
     }
   }
 
-  // `()` or `(^)`
+  // `Name { field1, field2 }` where the fields are labels of the expression.
+  fn parse_struct_fields(&mut self) -> rust::PResult<'a, Vec<Ident>> {
+    self.bump();
+    let mut fields = vec![];
+    while self.rp.token != rtok::CloseDelim(rust::DelimToken::Brace) {
+      fields.push(self.rp.parse_ident()?);
+      if self.rp.token == rtok::Comma {
+        self.bump();
+      }
+      else {
+        break;
+      }
+    }
+    self.rp.expect(&rtok::CloseDelim(rust::DelimToken::Brace))?;
+    Ok(fields)
+  }
+
+  /// The annotated types appear in the signatures of the generated functions, where the placeholder `_` is not allowed.
+  fn check_placeholder(&mut self, ty: &rust::Ty, rule_name: &str) {
+    struct PlaceholderFinder {
+      found: bool
+    }
+    impl<'a> rust::visit::Visitor<'a> for PlaceholderFinder {
+      fn visit_ty(&mut self, ty: &'a rust::Ty) {
+        if let rust::TyKind::Infer = ty.node {
+          self.found = true;
+        }
+        rust::visit::walk_ty(self, ty);
+      }
+    }
+    let mut finder = PlaceholderFinder { found: false };
+    rust::visit::Visitor::visit_ty(&mut finder, ty);
+    if finder.found {
+      self.rp.span_err(ty.span, format!(
+        "In rule {}: The type `{}` contains the placeholder `_` which can not be inferred by Oak since \
+         the type is used in the signature of the generated functions.\n\
+         Help: write the type in full, such as `HashMap<String, u32>`.",
+        rule_name, rust::ty_to_string(ty)).as_str());
+    }
+  }
+
+  // `()`, `(^)`, a collection type such as `e* -> String` or any Rust type such as `e -> Box<Expr>`.
   fn parse_type(&mut self, lo: BytePos, expr: usize, rule_name: &str) -> rust::PResult<'a, usize> {
     let token = self.rp.token.clone();
     match token {
-      rtok::OpenDelim(rust::DelimToken::Paren) => {
+      rtok::OpenDelim(rust::DelimToken::Paren) if self.is_oak_type() => {
         self.bump();
         let token = self.rp.token.clone();
         let ty =
@@ -153,11 +245,21 @@ impl<'a> Parser<'a>
         let hi = self.rp.span.hi();
         Ok(self.alloc_expr(lo, hi, TypeAscription(expr, ty)))
       }
+      _ if self.rp.token.can_begin_type() => {
+        let ty = self.rp.parse_ty()?;
+        let hi = self.rp.prev_span.hi();
+        self.check_placeholder(&ty, rule_name);
+        let ty = match self.repeated_expr(expr) {
+          Some(item) => IType::Regular(Type::Collection(item, ty)),
+          None => IType::rust_type(ty)
+        };
+        Ok(self.alloc_expr(lo, hi, TypeAscription(expr, ty)))
+      }
       _ => {
         let span = self.rp.span;
         self.rp.span_err(
           span,
-          format!("In rule {}: Unknown token after `->`. Use the arrow to annotate an expression with the unit type `()` or the invisible type `(^)`.",
+          format!("In rule {}: Unknown token after `->`. Use the arrow to annotate an expression with the unit type `()`, the invisible type `(^)` or a Rust type (e.g. `e -> Box<Expr>`).",
             rule_name).as_str()
         );
         Ok(expr)
@@ -165,6 +267,16 @@ impl<'a> Parser<'a>
     }
   }
 
+  /// The expression repeated by `expr` if it is a repetition.
+  fn repeated_expr(&self, expr: usize) -> Option<usize> {
+    match self.grammar.exprs[expr] {
+      ZeroOrMore(item)
+    | OneOrMore(item)
+    | CountedRepeat(item, _) => Some(item),
+      _ => None
+    }
+  }
+
   fn parse_spanned_expr(&mut self, rule_name: &str) -> rust::PResult<'a, usize> {
     let token = self.rp.token.clone();
     match token {
@@ -202,16 +314,46 @@ impl<'a> Parser<'a>
   fn parse_rule_prefixed(&mut self, rule_name: &str) -> rust::PResult<'a, Option<usize>> {
     let token = self.rp.token.clone();
     match token {
+      rtok::Ident(label) if !token.is_reserved_ident() && self.is_binding() => {
+        self.parse_binding(label, rule_name).map(Some)
+      }
       rtok::Not => {
         self.parse_prefix(rule_name, |e| NotPredicate(e), "A not predicate (`!expr`)").map(Some)
       }
       rtok::BinOp(rbtok::And) => {
         self.parse_prefix(rule_name, |e| AndPredicate(e), "An and predicate (`&expr`)").map(Some)
       }
+      rtok::Dollar => {
+        self.parse_prefix(rule_name, |e| Capture(e), "A capture (`$expr`)").map(Some)
+      }
+      // At the start of a branch, `..` spans the whole sequence (see `parse_spanned_expr`).
+      rtok::DotDot => {
+        self.parse_prefix(rule_name, |e| SpannedExpr(e), "A span expression (`..expr`)").map(Some)
+      }
       _ => self.parse_rule_suffixed(rule_name)
     }
   }
 
+  // `label:expr`
+  fn parse_binding(&mut self, label: Ident, rule_name: &str) -> rust::PResult<'a, usize> {
+    let lo = self.rp.span.lo();
+    self.bump();
+    self.bump();
+    match self.parse_rule_suffixed(rule_name)? {
+      Some(expr) => {
+        let hi = self.rp.prev_span.hi();
+        Ok(self.alloc_expr(lo, hi, Binding(label, expr)))
+      }
+      None => {
+        Err(self.fatal_error(
+          format!("In rule {}: The label `{}` is not followed by a valid expression \
+            (such as in `n:number`).",
+            rule_name, label).as_str()
+        ))
+      }
+    }
+  }
+
   fn parse_prefix<F>(&mut self, rule_name: &str, make_prefix: F, pred_name: &str) -> rust::PResult<'a, usize>
    where F: Fn(usize) -> Expression
   {
@@ -253,6 +395,13 @@ impl<'a> Parser<'a>
         self.bump();
         self.alloc_expr(lo, hi, ZeroOrOne(expr))
       },
+      rtok::OpenDelim(rust::DelimToken::Brace) => {
+        self.bump();
+        let count = self.rp.parse_ident()?;
+        self.rp.expect(&rtok::CloseDelim(rust::DelimToken::Brace))?;
+        let hi = self.rp.prev_span.hi();
+        self.alloc_expr(lo, hi, CountedRepeat(expr, count))
+      },
       _ => expr
     };
     Ok(Some(res))
@@ -277,6 +426,11 @@ impl<'a> Parser<'a>
         let cooked_lit = cook_lit(name);
         Some(self.last_respan(StrLiteral(cooked_lit)))
       },
+      rtok::Literal(rust::token::Lit::ByteStr(name),_) => {
+        self.bump();
+        let cooked_lit = cook_byte_lit(name);
+        Some(self.last_respan(ByteLiteral(cooked_lit)))
+      },
       rtok::Dot => {
         self.bump();
         Some(self.last_respan(AnySingleChar))
@@ -287,6 +441,20 @@ impl<'a> Parser<'a>
         self.rp.expect(&rtok::CloseDelim(rust::DelimToken::Paren))?;
         Some(res)
       },
+      rtok::Ident(_) if !token.is_reserved_ident() && self.is_token_pattern() => {
+        let lo = self.rp.span.lo();
+        let pattern = self.rp.parse_pat()?;
+        let hi = self.rp.prev_span.hi();
+        Some(self.alloc_expr(lo, hi, TokenPattern(pattern)))
+      },
+      rtok::Ident(_) if self.is_precedence_expr() => {
+        Some(self.parse_precedence(rule_name)?)
+      },
+      rtok::Ident(ident) if IndentKind::from_ident(ident).is_some() && !self.is_rule_lhs() => {
+        self.bump();
+        let kind = IndentKind::from_ident(ident).unwrap();
+        Some(self.last_respan(Indentation(kind)))
+      },
       rtok::Ident(ident) if !token.is_reserved_ident() => {
         if self.is_rule_lhs() { None }
         else {
@@ -311,18 +479,66 @@ impl<'a> Parser<'a>
           }
         }
       },
-      rtok::DotDot => {
-        return Err(self.fatal_error(
-          format!("In rule {}: A span expression `.. e1 e2` must always start a sequence. \
-            You can force this by grouping the spanned expression with parenthesis: `e1 (.. e2)`.",
-            rule_name).as_str()
-        ));
-      }
       _ => { None }
     };
     Ok(res)
   }
 
+  // `precedence atom { left op > action, right op > action, prefix op > action, postfix op > action }`
+  fn parse_precedence(&mut self, rule_name: &str) -> rust::PResult<'a, usize> {
+    let lo = self.rp.span.lo();
+    self.bump();
+    let atom_name = self.rp.parse_ident()?;
+    let atom = self.last_respan(NonTerminalSymbol(atom_name));
+    self.rp.expect(&rtok::OpenDelim(rust::DelimToken::Brace))?;
+    let mut levels = vec![];
+    while self.rp.token != rtok::CloseDelim(rust::DelimToken::Brace) {
+      levels.push(self.parse_precedence_level(rule_name)?);
+      if self.rp.token == rtok::Comma {
+        self.bump();
+      }
+      else {
+        break;
+      }
+    }
+    self.rp.expect(&rtok::CloseDelim(rust::DelimToken::Brace))?;
+    let hi = self.rp.prev_span.hi();
+    if levels.is_empty() {
+      self.rp.span_err(
+        Span::new(lo, hi, NO_EXPANSION),
+        format!("In rule {}: A precedence expression must declare at least one operator level \
+          (such as in `precedence factor {{ left add_op > bin_expr }}`).",
+          rule_name).as_str());
+      return Ok(atom);
+    }
+    Ok(self.alloc_expr(lo, hi, Precedence(atom, levels)))
+  }
+
+  fn parse_precedence_level(&mut self, rule_name: &str) -> rust::PResult<'a, PrecedenceLevel> {
+    let assoc = self.parse_associativity(rule_name)?;
+    let op = self.parse_rule_seq(rule_name)?;
+    self.rp.expect(&rtok::Gt)?;
+    let action = self.rp.parse_ident()?;
+    Ok(PrecedenceLevel::new(assoc, op, action))
+  }
+
+  fn parse_associativity(&mut self, rule_name: &str) -> rust::PResult<'a, Associativity> {
+    let assoc = self.rp.parse_ident()?;
+    match &*assoc.name.as_str() {
+      "left" => Ok(Associativity::Left),
+      "right" => Ok(Associativity::Right),
+      "prefix" => Ok(Associativity::Prefix),
+      "postfix" => Ok(Associativity::Postfix),
+      _ => {
+        let span = self.rp.prev_span;
+        Err(self.rp.span_fatal(span,
+          format!("In rule {}: Unknown operator kind `{}` in a precedence level. \
+            It must be one of `left`, `right`, `prefix` or `postfix`.",
+            rule_name, assoc).as_str()))
+      }
+    }
+  }
+
   fn parse_char_class(&mut self, rule_name: &str) -> rust::PResult<'a, usize> {
     let token = self.rp.token.clone();
     match token {
@@ -335,19 +551,34 @@ impl<'a> Parser<'a>
             "Empty character classes is forbidden. For empty expression \
             you can use the empty string literal `\"\"`.");
         }
-        Ok(self.parse_set_of_char_range(&cooked_lit, rule_name))
+        Ok(self.parse_set_of_char_range(&cooked_lit, AtomKind::Char, rule_name, span))
+      },
+      // The bytes are read as the characters `0-255` so they share the parsing of character ranges.
+      rtok::Literal(rust::token::Lit::ByteStr(name),_) => {
+        let span = self.rp.span;
+        self.bump();
+        let cooked_lit: String = cook_byte_lit(name).into_iter()
+          .map(|byte| byte as char)
+          .collect();
+        if cooked_lit.is_empty() {
+          self.rp.span_err(span,
+            "Empty byte classes are forbidden. For empty expression \
+            you can use the empty string literal `\"\"`.");
+        }
+        Ok(self.parse_set_of_char_range(&cooked_lit, AtomKind::Byte, rule_name, span))
       },
       _ => {
         Err(self.fatal_error(
           format!("In rule {}: Unexpected character in this character class. \
-            `[` must only be followed by a string literal (such as in `[\"a-z\"]`)",
+            `[` must only be followed by a string literal (such as in `[\"a-z\"]` or `[b\"\\x00-\\x1f\"]`)",
             rule_name).as_str()
         ))
       }
     }
   }
 
-  fn parse_set_of_char_range(&mut self, ranges: &String, rule_name: &str) -> usize {
+  /// The span of the class is the one of its string literal, the analyses locate the characters of the intervals inside it.
+  fn parse_set_of_char_range(&mut self, ranges: &String, kind: AtomKind, rule_name: &str, span: Span) -> usize {
     let mut ranges = ranges.chars().peekable();
     let mut intervals = vec![];
     match ranges.peek() {
@@ -358,24 +589,22 @@ impl<'a> Parser<'a>
       _ => ()
     }
     loop {
-      let char_set = self.parse_char_range(&mut ranges, rule_name);
+      let char_set = self.parse_char_range(&mut ranges, rule_name, span);
       intervals.extend_from_slice(char_set.as_slice());
       if char_set.is_empty() {
           break;
       }
     }
-    let sp = self.rp.span;
-    self.alloc_expr(sp.lo(), sp.hi(), CharacterClass(CharacterClassExpr::new(intervals)))
+    self.alloc_expr(span.lo(), span.hi(), CharacterClass(CharacterClassExpr::with_kind(intervals, kind)))
   }
 
-  fn parse_char_range<'b>(&mut self, ranges: &mut Peekable<Chars<'b>>, rule_name: &str) -> Vec<CharacterInterval> {
+  fn parse_char_range<'b>(&mut self, ranges: &mut Peekable<Chars<'b>>, rule_name: &str, span: Span) -> Vec<CharacterInterval> {
     let mut res = vec![];
     let separator_err = format!(
       "In rule {}: Unexpected separator `-`. Put it in the start or the end if you want \
       to accept it as a character in the set. Otherwise, you should only use it for \
       character intervals as in `[\"a-z\"]`.",
       rule_name);
-    let span = self.rp.span;
     let lo = ranges.next();
     // Twisted logic due to the fact that `peek` borrows the ranges...
     let lo = {
@@ -409,14 +638,84 @@ impl<'a> Parser<'a>
     res
   }
 
-  fn is_rule_lhs(&mut self) -> bool {
+  fn is_precedence_expr(&mut self) -> bool {
     let token = self.rp.token.clone();
-    if let rtok::Ident(_) = token {
-      !token.is_reserved_ident() &&
-      self.rp.look_ahead(1, |t| match t { &rtok::Eq => true, _ => false})
+    if let rtok::Ident(ident) = token {
+      &*ident.name.as_str() == "precedence" &&
+      self.rp.look_ahead(2, |t| match t {
+        &rtok::OpenDelim(rust::DelimToken::Brace) => true,
+        _ => false
+      })
     }
     else {
       false
     }
   }
+
+  // `()` or `(^)`, other parenthesized types are Rust types.
+  fn is_oak_type(&mut self) -> bool {
+    self.rp.look_ahead(1, |t| match t {
+      &rtok::BinOp(rbtok::Caret)
+    | &rtok::CloseDelim(rust::DelimToken::Paren) => true,
+      _ => false
+    })
+  }
+
+  // `label:` but not `rule: Type =`
+  fn is_binding(&mut self) -> bool {
+    self.rp.look_ahead(1, |t| t == &rtok::Colon) && !self.is_rule_lhs()
+  }
+
+  // `Token::Variant` or `Token::Variant(..)`: a path starts a pattern on the tokens of the stream.
+  fn is_token_pattern(&mut self) -> bool {
+    self.rp.look_ahead(1, |t| t == &rtok::ModSep)
+  }
+
+  // `#[attr]* ident =`
+  fn is_rule_lhs(&mut self) -> bool {
+    // An outer attribute is a `#` followed by a bracketed token tree.
+    let mut dist = 0;
+    while self.rp.look_ahead(dist, |t| t == &rtok::Pound) {
+      dist += 2;
+    }
+    let is_ident = self.rp.look_ahead(dist, |t| match t {
+      &rtok::Ident(_) => !t.is_reserved_ident(),
+      _ => false
+    });
+    is_ident &&
+    (self.rp.look_ahead(dist + 1, |t| match t { &rtok::Eq => true, _ => false})
+     || self.is_typed_rule_lhs(dist + 1))
+  }
+
+  // `: Type =` starting at `dist`. The type is scanned until `=` and rejected as soon as a token cannot continue a type, such as in the bindings `n:number ":"` or `e:expr > f`.
+  fn is_typed_rule_lhs(&mut self, mut dist: usize) -> bool {
+    if !self.rp.look_ahead(dist, |t| t == &rtok::Colon) {
+      return false;
+    }
+    dist += 1;
+    // Angle brackets are not delimiters so we count them.
+    let mut depth = 0;
+    // `true` if the previous token ends a type (or a segment of it).
+    let mut after_ty = false;
+    loop {
+      let token = self.rp.look_ahead(dist, |t| t.clone());
+      match token {
+        rtok::Eq => return depth == 0 && after_ty,
+        rtok::Ident(_)
+      | rtok::OpenDelim(_) if !after_ty => after_ty = true,
+        rtok::Lifetime(_)
+      | rtok::BinOp(rbtok::And) if !after_ty => (),
+        rtok::ModSep if after_ty => after_ty = false,
+        rtok::Comma if depth > 0 => after_ty = false,
+        rtok::Lt if after_ty => {
+          depth += 1;
+          after_ty = false;
+        }
+        rtok::Gt if depth > 0 && after_ty => depth -= 1,
+        rtok::BinOp(rbtok::Shr) if depth > 1 && after_ty => depth -= 2,
+        _ => return false
+      }
+      dist += 1;
+    }
+  }
 }
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 pub use std::string::String;
-pub use rust::{Ident, Name, Span, str_lit};
+pub use rust::{Ident, Name, Span, str_lit, byte_str_lit};
 use rust;
 use std::ops::Deref;
 
@@ -25,10 +25,27 @@ pub fn string_to_ident(cx: &rust::ExtCtxt, name: String) -> Ident {
   cx.ident_of(name.as_str())
 }
 
+/// `expr_list` gives `ExprList`.
+pub fn camel_case(cx: &rust::ExtCtxt, name: String) -> Ident {
+  let camel: String = name.split('_')
+    .filter(|part| !part.is_empty())
+    .map(|part| {
+      let mut chars = part.chars();
+      let first = chars.next().unwrap();
+      first.to_uppercase().chain(chars).collect::<String>()
+    })
+    .collect();
+  string_to_ident(cx, camel)
+}
+
 pub fn cook_lit(name: Name) -> String {
   str_lit(name.to_string().as_str(), None)
 }
 
+pub fn cook_byte_lit(name: Name) -> Vec<u8> {
+  byte_str_lit(name.to_string().as_str()).to_vec()
+}
+
 pub trait ItemIdent
 {
   fn ident(&self) -> Ident;
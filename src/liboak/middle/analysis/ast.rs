@@ -17,6 +17,7 @@ pub use visitor::*;
 pub use front::ast::FExpressionInfo;
 
 use std::default::Default;
+use std::collections::HashMap;
 
 pub type AGrammar<'a, 'b> = Grammar<'a, 'b, FExpressionInfo>;
 
@@ -29,20 +30,197 @@ impl<'a, 'b> AGrammar<'a, 'b>
   pub fn merge_print_typing(&mut self, level: PrintLevel) {
     self.attributes.print_typing = self.attributes.print_typing.merge(level);
   }
+
+  pub fn alloc_expr(&mut self, span: Span, expr: Expression) -> usize {
+    let expr_idx = self.exprs.len();
+    self.exprs.push(expr);
+    self.exprs_info.push(FExpressionInfo { span: span });
+    expr_idx
+  }
+
+  /// The expression parsed by `expr` without the constructions building its value.
+  pub fn parsed_expr(&self, expr: usize) -> usize {
+    match self.exprs[expr] {
+      Expression::SemanticAction(child, _)
+    | Expression::StructAction(child, _, _)
+    | Expression::TypeAscription(child, _)
+    | Expression::Binding(_, child)
+    | Expression::AstNode(child, _, _)
+    | Expression::SpannedExpr(child)
+    | Expression::Capture(child) => self.parsed_expr(child),
+      _ => expr
+    }
+  }
+
+  /// The elements of `expr` if it is a sequence (possibly under an action or a type ascription), otherwise `expr` itself.
+  pub fn sequence_elements(&self, expr: usize) -> Vec<usize> {
+    match self.exprs[self.parsed_expr(expr)] {
+      Expression::Sequence(ref seq) => seq.clone(),
+      _ => vec![expr]
+    }
+  }
+
+  /// A rule (other than the skip rule) or a repetition can consume an arbitrarily long input.
+  pub fn may_consume_long_input(&self, expr: usize) -> bool {
+    let skip = self.attributes.skip.map(|skip| skip.node);
+    match self.exprs[self.parsed_expr(expr)] {
+      Expression::NonTerminalSymbol(rule) => Some(rule) != skip,
+      Expression::ZeroOrMore(_)
+    | Expression::OneOrMore(_)
+    | Expression::CountedRepeat(_, _) => true,
+      Expression::ZeroOrOne(child)
+    | Expression::NotPredicate(child)
+    | Expression::AndPredicate(child) => self.may_consume_long_input(child),
+      Expression::Sequence(ref children)
+    | Expression::Choice(ref children) => children.iter().any(|&child| self.may_consume_long_input(child)),
+      _ => false
+    }
+  }
 }
 
 pub struct GrammarAttributes
 {
   pub print_code: PrintLevel,
-  pub print_typing: PrintLevel
+  pub print_typing: PrintLevel,
+  /// The rule declared with `#![skip(rule)]`, it is implicitly inserted between tokens.
+  pub skip: Option<SpannedIdent>,
+  /// `#![derive_ast]`: a type is generated for every sequence or choice rule without action.
+  pub derive_ast: bool,
+  /// `#![auto_box]`: the entry point of a recursive value type is wrapped into a generated type, so the recursion goes through a `Box`.
+  pub auto_box: bool,
+  /// The traits derived by the generated types, listed in `#![derive_ast(Debug, Clone)]` or `#![auto_box(Debug, Clone)]`.
+  pub ast_derives: Vec<Ident>,
+  /// `#![with_spans]`: the span of the matched expression is passed as first argument to every semantic action.
+  pub with_spans: bool,
+  /// `#![grammar_stats]`: prints the nullability, FIRST set, lookahead and recursion of every rule.
+  pub grammar_stats: bool,
+  /// `#![allow(lint)]`, `#![warn(lint)]` and `#![deny(lint)]`.
+  pub lints: HashMap<Lint, LintLevel>,
+  pub rules: HashMap<Ident, RuleAttributes>
+}
 
+impl GrammarAttributes
+{
+  pub fn is_atomic(&self, rule: Ident) -> bool {
+    self.rules.get(&rule).map_or(false, |attrs| attrs.atomic)
+  }
+
+  pub fn is_entry(&self, rule: Ident) -> bool {
+    self.rules.get(&rule).map_or(false, |attrs| attrs.entry)
+  }
+
+  /// The level of `lint` inside `rule`: the level given on the rule overrides the one of the grammar, and lints are warnings by default.
+  pub fn lint_level(&self, lint: Lint, rule: Option<Ident>) -> LintLevel {
+    self.rule_lint_level(lint, rule)
+      .or_else(|| self.grammar_lint_level(lint))
+      .unwrap_or(LintLevel::Warn)
+  }
+
+  /// The level of `lint` set with `#[allow(..)]`, `#[warn(..)]` or `#[deny(..)]` on `rule`.
+  pub fn rule_lint_level(&self, lint: Lint, rule: Option<Ident>) -> Option<LintLevel> {
+    rule.and_then(|rule| self.rules.get(&rule))
+      .and_then(|attrs| attrs.lints.get(&lint))
+      .cloned()
+  }
+
+  /// The level of `lint` set on the grammar.
+  pub fn grammar_lint_level(&self, lint: Lint) -> Option<LintLevel> {
+    self.lints.get(&lint).cloned()
+  }
 }
 
 impl Default for GrammarAttributes {
   fn default() -> Self {
     GrammarAttributes {
       print_code: PrintLevel::default(),
-      print_typing: PrintLevel::default()
+      print_typing: PrintLevel::default(),
+      skip: None,
+      derive_ast: false,
+      auto_box: false,
+      ast_derives: vec![],
+      with_spans: false,
+      grammar_stats: false,
+      lints: HashMap::new(),
+      rules: HashMap::new()
+    }
+  }
+}
+
+#[derive(Clone, Default)]
+pub struct RuleAttributes
+{
+  /// `#[atomic]`: the skip rule is not inserted inside this rule.
+  pub atomic: bool,
+  /// `#[entry]`: this rule is a public entry point of the grammar, it is not reported when it is never referenced.
+  pub entry: bool,
+  /// `#[allow(lint)]`, `#[warn(lint)]` and `#[deny(lint)]` on this rule.
+  pub lints: HashMap<Lint, LintLevel>
+}
+
+/// The warnings of the analyses that can be controlled with `allow`, `warn` and `deny` attributes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Lint
+{
+  UselessChaining,
+  UnreachableAlternative,
+  RecursiveType,
+  UnusedRule,
+  UnusedAction,
+  Backtracking,
+  LeftFactoring,
+  DuplicateRule,
+  DuplicateAlternative,
+  CharClassOverlap,
+  SingleCharClass
+}
+
+impl Lint
+{
+  pub fn all() -> Vec<Lint> {
+    use self::Lint::*;
+    vec![UselessChaining, UnreachableAlternative, RecursiveType, UnusedRule, UnusedAction,
+      Backtracking, LeftFactoring, DuplicateRule, DuplicateAlternative, CharClassOverlap,
+      SingleCharClass]
+  }
+
+  pub fn from_name(name: &str) -> Option<Lint> {
+    Lint::all().into_iter().find(|lint| lint.name() == name)
+  }
+
+  pub fn name(self) -> &'static str {
+    use self::Lint::*;
+    match self {
+      UselessChaining => "useless_chaining",
+      UnreachableAlternative => "unreachable_alternative",
+      RecursiveType => "recursive_type",
+      UnusedRule => "unused_rule",
+      UnusedAction => "unused_action",
+      Backtracking => "backtracking",
+      LeftFactoring => "left_factoring",
+      DuplicateRule => "duplicate_rule",
+      DuplicateAlternative => "duplicate_alternative",
+      CharClassOverlap => "char_class_overlap",
+      SingleCharClass => "single_char_class"
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LintLevel
+{
+  Allow,
+  Warn,
+  Deny
+}
+
+impl LintLevel
+{
+  pub fn from_name(name: &str) -> Option<LintLevel> {
+    match name {
+      "allow" => Some(LintLevel::Allow),
+      "warn" => Some(LintLevel::Warn),
+      "deny" => Some(LintLevel::Deny),
+      _ => None
     }
   }
 }
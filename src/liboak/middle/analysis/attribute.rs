@@ -14,12 +14,17 @@
 
 use middle::analysis::ast::*;
 
-use rust::{MetaItemKind, MetaItem};
+use rust;
+use rust::{MetaItemKind, MetaItem, NestedMetaItem};
 
 pub fn decorate_with_attributes<'a, 'b>(mut grammar: AGrammar<'a, 'b>,
-  attributes: Vec<Attribute>) -> Partial<AGrammar<'a, 'b>>
+  attributes: Vec<Attribute>, rules_attributes: Vec<(Ident, Vec<Attribute>)>)
+  -> Partial<AGrammar<'a, 'b>>
 {
   merge_grammar_attributes(&mut grammar, attributes);
+  for (rule, attrs) in rules_attributes {
+    merge_rule_attributes(&mut grammar, rule, attrs);
+  }
   Partial::Value(grammar)
 }
 
@@ -44,6 +49,32 @@ fn merge_grammar_attr<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, meta_item: MetaIte
     },
     &MetaItemKind::Word if meta_item.name == "show_typing" => {
       grammar.merge_print_typing(PrintLevel::Show);
+    },
+    &MetaItemKind::Word if meta_item.name == "derive_ast" => {
+      grammar.attributes.derive_ast = true;
+    },
+    &MetaItemKind::List(ref items) if meta_item.name == "derive_ast" => {
+      grammar.attributes.derive_ast = true;
+      merge_ast_derives(grammar, items);
+    },
+    &MetaItemKind::Word if meta_item.name == "auto_box" => {
+      grammar.attributes.auto_box = true;
+    },
+    &MetaItemKind::List(ref items) if meta_item.name == "auto_box" => {
+      grammar.attributes.auto_box = true;
+      merge_ast_derives(grammar, items);
+    },
+    &MetaItemKind::Word if meta_item.name == "with_spans" => {
+      grammar.attributes.with_spans = true;
+    },
+    &MetaItemKind::Word if meta_item.name == "grammar_stats" => {
+      grammar.attributes.grammar_stats = true;
+    },
+    &MetaItemKind::List(ref items) if meta_item.name == "skip" => {
+      merge_skip_rule(grammar, meta_item.span, items);
+    },
+    &MetaItemKind::List(ref items) if lint_level(&meta_item).is_some() => {
+      merge_lint_levels(grammar, None, lint_level(&meta_item).unwrap(), items);
     },
       &MetaItemKind::Word
     | &MetaItemKind::List(_)
@@ -54,3 +85,100 @@ fn merge_grammar_attr<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, meta_item: MetaIte
     }
   }
 }
+
+fn merge_skip_rule<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, span: Span,
+  items: &Vec<NestedMetaItem>)
+{
+  match (items.len(), items.first().and_then(|item| item.word())) {
+    (1, Some(rule)) => {
+      if grammar.attributes.skip.is_some() {
+        grammar.span_warn(span, format!(
+          "The skip rule is already declared: this attribute overrides the previous one."));
+      }
+      let rule = string_to_ident(grammar.cx, rule.name.to_string());
+      grammar.attributes.skip = Some(rust::respan(span, rule));
+    }
+    _ => {
+      grammar.span_err(span, format!(
+        "`skip` expects exactly one rule name, such as in `#![skip(spacing)]`."));
+    }
+  }
+}
+
+/// The traits listed in `#![derive_ast(Debug, Clone)]` or `#![auto_box(Debug, Clone)]` are derived by the generated types.
+fn merge_ast_derives<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, items: &Vec<NestedMetaItem>) {
+  for item in items {
+    match item.word() {
+      Some(word) => {
+        let derive = string_to_ident(grammar.cx, word.name.to_string());
+        if !grammar.attributes.ast_derives.contains(&derive) {
+          grammar.attributes.ast_derives.push(derive);
+        }
+      }
+      None => {
+        grammar.span_err(item.span, format!(
+          "Expected the name of a trait to derive, such as in `#![derive_ast(Debug, Clone)]`."));
+      }
+    }
+  }
+}
+
+fn lint_level(meta_item: &MetaItem) -> Option<LintLevel> {
+  LintLevel::from_name(&meta_item.name.to_string())
+}
+
+/// `#![allow(lint, ...)]` on the grammar or `#[allow(lint, ...)]` on `rule`, and similarly for `warn` and `deny`.
+fn merge_lint_levels<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, rule: Option<Ident>,
+  level: LintLevel, items: &Vec<NestedMetaItem>)
+{
+  for item in items {
+    match item.word().and_then(|word| Lint::from_name(&word.name.to_string())) {
+      Some(lint) => {
+        let lints = match rule {
+          Some(rule) => &mut grammar.attributes.rules.entry(rule)
+            .or_insert(RuleAttributes::default()).lints,
+          None => &mut grammar.attributes.lints
+        };
+        lints.insert(lint, level);
+      }
+      None => {
+        let lints: Vec<_> = Lint::all().into_iter()
+          .map(|lint| format!("`{}`", lint.name()))
+          .collect();
+        grammar.span_warn(item.span, format!(
+          "Unknown lint: it will be ignored. The available lints are {}.", lints.join(", ")));
+      }
+    }
+  }
+}
+
+fn merge_rule_attributes<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, rule: Ident, attrs: Vec<Attribute>) {
+  for attr in attrs {
+    attr.meta().map(|meta_item| {
+        merge_rule_attr(grammar, rule, meta_item);
+    });
+  }
+}
+
+fn merge_rule_attr<'a, 'b>(grammar: &mut AGrammar<'a, 'b>, rule: Ident, meta_item: MetaItem) {
+  match &meta_item.node {
+    &MetaItemKind::Word if meta_item.name == "atomic" => {
+      grammar.attributes.rules.entry(rule).or_insert(RuleAttributes::default())
+        .atomic = true;
+    },
+    &MetaItemKind::Word if meta_item.name == "entry" => {
+      grammar.attributes.rules.entry(rule).or_insert(RuleAttributes::default())
+        .entry = true;
+    },
+    &MetaItemKind::List(ref items) if lint_level(&meta_item).is_some() => {
+      merge_lint_levels(grammar, Some(rule), lint_level(&meta_item).unwrap(), items);
+    },
+      &MetaItemKind::Word
+    | &MetaItemKind::List(_)
+    | &MetaItemKind::NameValue(_) => {
+      grammar.span_warn(meta_item.span, format!(
+        "Unknown attribute `{}` on rule `{}`: it will be ignored.",
+        meta_item.name, rule));
+    }
+  }
+}
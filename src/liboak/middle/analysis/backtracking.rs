@@ -0,0 +1,121 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Estimates the risk of costly backtracking, Oak does not memoize the rules so an input can be parsed many times. The lint is based on the FIRST sets of the expressions (see `first_set`):
+//!
+//! * a repetition nested in a repeated expression is reported if the input it repeats can also start the expressions following it in the enclosing repetition, such as `(a*)*` or `(a* a?)*`: the same input can be split in many ways between the two repetitions. `(a* b)*` is not reported when `a` and `b` start differently since the input consumed by `a*` is then read once.
+//! * a choice directly repeated, such as `(a / c)*`, is reported if an alternative consuming a long input (a rule or a repetition) starts like a next alternative: the input is parsed again by the next alternative at every iteration. The alternatives starting with the same expressions, such as `(a / a b)*`, are reported in `left_factoring`.
+
+use middle::analysis::ast::*;
+use middle::analysis::first_set::*;
+
+pub struct Backtracking<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  first_sets: FirstSets<'a, 'b, 'c>
+}
+
+impl<'a, 'b, 'c> Backtracking<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if Backtracking::check_backtracking(&grammar) {
+      Partial::Nothing
+    } else {
+      Partial::Value(grammar)
+    }
+  }
+
+  /// Returns `true` if a denied lint is raised.
+  fn check_backtracking(grammar: &'c AGrammar<'a, 'b>) -> bool {
+    let analyser = Backtracking {
+      grammar: grammar,
+      first_sets: FirstSets::new(grammar, 1)
+    };
+    let mut denied = false;
+    for (expr_idx, expr) in grammar.exprs.iter().enumerate() {
+      denied |= match expr {
+        &Expression::ZeroOrMore(child)
+      | &Expression::OneOrMore(child) => analyser.check_repetition(expr_idx, child),
+        _ => false
+      };
+    }
+    denied
+  }
+
+  fn check_repetition(&self, this: usize, child: usize) -> bool {
+    self.check_nested_repetition(this, child) | self.check_repeated_choice(this, child)
+  }
+
+  /// In `(e1 r e2)*` where `r` is the repetition `e*`, the input read by `e` must not start `e2 e1 r e2`, the expressions following `r` until its next iteration.
+  fn check_nested_repetition(&self, this: usize, child: usize) -> bool {
+    let seq = self.grammar.sequence_elements(child);
+    let mut denied = false;
+    for (i, &e) in seq.iter().enumerate() {
+      let repeated = match self.grammar.exprs[self.grammar.parsed_expr(e)] {
+        Expression::ZeroOrMore(repeated)
+      | Expression::OneOrMore(repeated) => repeated,
+        _ => continue
+      };
+      let follow = concat(self.first_sets.of_sequence(&seq[i+1..]),
+        self.first_sets.of_expr(child), 1);
+      if overlap(&self.first_sets.of_expr(repeated), &follow) {
+        denied |= self.grammar.lint(Lint::Backtracking, self.grammar.rule_of_expr(this), vec![
+          (self.grammar[e].span(), format!(
+            "This repetition is nested in a repetition and the input it repeats can also start the \
+             expressions following it: the same input can be split in many ways between the two \
+             repetitions and is read again after backtracking.\n\
+             Help: make the inner repetition and the expressions following it start differently, \
+             or memoize the rule.")),
+          (self.grammar[this].span(), format!("The enclosing repetition."))
+        ]);
+      }
+    }
+    denied
+  }
+
+  /// In `(a / b)*`, an alternative `a` consuming a long input and starting like a next alternative `b` is parsed again by `b` at every iteration. The alternatives sharing a prefix are left to `left_factoring`.
+  fn check_repeated_choice(&self, this: usize, child: usize) -> bool {
+    let alternatives = match self.grammar.exprs[self.grammar.parsed_expr(child)] {
+      Expression::Choice(ref alternatives) => alternatives.clone(),
+      _ => return false
+    };
+    let mut denied = false;
+    for (i, &alt) in alternatives.iter().enumerate() {
+      if !self.grammar.may_consume_long_input(alt) {
+        continue;
+      }
+      let first = self.first_sets.of_expr(alt);
+      let next = alternatives[i+1..].iter().find(|&&next|
+        overlap(&first, &self.first_sets.of_expr(next)) && !self.same_start(alt, next));
+      if let Some(&next) = next {
+        denied |= self.grammar.lint(Lint::Backtracking, self.grammar.rule_of_expr(this), vec![
+          (self.grammar[next].span(), format!(
+            "This alternative of a repeated choice can start like a previous alternative consuming \
+             a long input: when the previous alternative fails, its input is parsed again at every \
+             iteration.\n\
+             Help: make the alternatives start differently, or memoize the rule.")),
+          (self.grammar[alt].span(), format!("The previous alternative.")),
+          (self.grammar[this].span(), format!("The enclosing repetition."))
+        ]);
+      }
+    }
+    denied
+  }
+
+  fn same_start(&self, alt1: usize, alt2: usize) -> bool {
+    let e1 = self.grammar.parsed_expr(self.grammar.sequence_elements(alt1)[0]);
+    let e2 = self.grammar.parsed_expr(self.grammar.sequence_elements(alt2)[0]);
+    self.grammar.expr_eq(e1, e2)
+  }
+}
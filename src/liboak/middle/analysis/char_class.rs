@@ -0,0 +1,252 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validates the intervals of the character classes:
+//!
+//! * an inverted interval such as `["z-a"]` is empty and is reported as an error,
+//! * overlapping or duplicate intervals such as `["a-mf-z"]` are reported by the lint `char_class_overlap`,
+//! * a class containing a single character such as `[" "]` is reported by the lint `single_char_class` when its value is not used (under `-> ()`, `-> (^)` or a predicate), since the literal `" "` is equivalent.
+//!
+//! The diagnostics point at the characters of the interval inside the string literal of the class. The intervals are located by reading the source of the literal again, if it is not available (e.g. the grammar is generated by a macro), the whole class is pointed.
+
+use middle::analysis::ast::*;
+use rust::{BytePos, NO_EXPANSION};
+
+/// An interval of a class and the span of its characters in the source.
+struct LocatedInterval
+{
+  lo: char,
+  hi: char,
+  span: Span
+}
+
+pub struct CharClassAnalysis<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  rule: Option<Ident>,
+  value_unused: bool,
+  has_error: bool,
+  has_denied: bool
+}
+
+impl<'a, 'b, 'c> CharClassAnalysis<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if CharClassAnalysis::check_classes(&grammar) {
+      Partial::Nothing
+    } else {
+      Partial::Value(grammar)
+    }
+  }
+
+  /// Returns `true` if an error or a denied lint is raised.
+  fn check_classes(grammar: &'c AGrammar<'a, 'b>) -> bool {
+    let mut analyser = CharClassAnalysis {
+      grammar: grammar,
+      rule: None,
+      value_unused: false,
+      has_error: false,
+      has_denied: false
+    };
+    for rule in &grammar.rules {
+      analyser.rule = Some(rule.ident());
+      analyser.visit_expr(rule.expr_idx);
+    }
+    analyser.has_error || analyser.has_denied
+  }
+
+  fn check_class(&mut self, this: usize, class: CharacterClassExpr) {
+    let span = self.grammar[this].span();
+    let intervals = self.locate_intervals(span, &class);
+    let rule = self.rule.map_or(String::new(), |rule| rule.to_string());
+    for (j, interval) in intervals.iter().enumerate() {
+      if interval.lo > interval.hi {
+        self.has_error = true;
+        self.grammar.span_err(interval.span, format!(
+          "In rule {}: the interval `{}-{}` is empty because `{}` comes after `{}`.\n\
+           Help: write it `{}-{}`.",
+          rule, escape(interval.lo), escape(interval.hi), escape(interval.lo), escape(interval.hi),
+          escape(interval.hi), escape(interval.lo)));
+        continue;
+      }
+      let overlapped = intervals[..j].iter()
+        .filter(|earlier| earlier.lo <= earlier.hi)
+        .find(|earlier| earlier.lo <= interval.hi && interval.lo <= earlier.hi);
+      if let Some(earlier) = overlapped {
+        let msg =
+          if earlier.lo == interval.lo && earlier.hi == interval.hi {
+            format!("In rule {}: `{}` is already in this character class.\nHelp: remove it.",
+              rule, display_interval(interval.lo, interval.hi))
+          }
+          else {
+            format!("In rule {}: the interval `{}` overlaps the interval `{}` of this character class.\n\
+              Help: merge them into `{}`.", rule, display_interval(interval.lo, interval.hi),
+              display_interval(earlier.lo, earlier.hi),
+              display_interval(earlier.lo.min(interval.lo), earlier.hi.max(interval.hi)))
+          };
+        let denied = self.grammar.lint(Lint::CharClassOverlap, self.rule, vec![
+          (interval.span, msg),
+          (earlier.span, format!("The characters are first included here."))
+        ]);
+        self.has_denied |= denied;
+      }
+    }
+    let single = intervals.first().map(|first| first.lo).and_then(|c|
+      if intervals.iter().all(|interval| interval.lo == c && interval.hi == c) { Some(c) } else { None });
+    if let (Some(c), true) = (single, self.value_unused) {
+      let literal = match class.kind {
+        AtomKind::Char => format!("{:?}", c.to_string()),
+        AtomKind::Byte => format!("b\"{}\"", escape_byte(c as u8))
+      };
+      let denied = self.grammar.lint(Lint::SingleCharClass, self.rule, vec![(span, format!(
+        "In rule {}: this character class only contains `{}` and its value is not used: \
+         it is equivalent to the literal {}.\n\
+         Help: write {} instead.", rule, escape(c), literal, literal))]);
+      self.has_denied |= denied;
+    }
+  }
+
+  /// Splits the literal of the class into its intervals, similarly to the parser, and attaches to each interval the span of its characters. The intervals are only located if the source matches the parsed intervals.
+  fn locate_intervals(&self, span: Span, class: &CharacterClassExpr) -> Vec<LocatedInterval> {
+    let located = self.grammar.snippet(span)
+      .and_then(|snippet| literal_chars(&snippet))
+      .map(|chars| split_intervals(&chars));
+    let matches_class = |located: &Vec<(char, char, usize, usize)>| located.len() == class.intervals.len()
+      && located.iter().zip(class.intervals.iter())
+           .all(|(&(lo, hi, _, _), interval)| lo == interval.lo && hi == interval.hi);
+    match located {
+      Some(ref located) if matches_class(located) => {
+        located.iter().map(|&(lo, hi, start, end)| LocatedInterval {
+          lo: lo,
+          hi: hi,
+          span: Span::new(BytePos(span.lo().0 + start as u32), BytePos(span.lo().0 + end as u32), NO_EXPANSION)
+        }).collect()
+      }
+      _ => {
+        class.intervals.iter().map(|interval| LocatedInterval {
+          lo: interval.lo,
+          hi: interval.hi,
+          span: span
+        }).collect()
+      }
+    }
+  }
+}
+
+/// The characters of a string or byte string literal with their byte offsets `[start, end)` in `literal`. Returns `None` if an escape sequence is not recognized.
+fn literal_chars(literal: &str) -> Option<Vec<(char, usize, usize)>> {
+  let start = literal.find('"')? + 1;
+  if !literal.ends_with('"') || literal.len() <= start {
+    return None;
+  }
+  let body = &literal[start..literal.len()-1];
+  let mut chars = vec![];
+  let mut iter = body.char_indices().peekable();
+  while let Some((i, c)) = iter.next() {
+    let (c, end) =
+      if c == '\\' {
+        let (_, escaped) = iter.next()?;
+        match escaped {
+          'n' => ('\n', i + 2),
+          'r' => ('\r', i + 2),
+          't' => ('\t', i + 2),
+          '0' => ('\0', i + 2),
+          '\\' | '\'' | '"' => (escaped, i + 2),
+          'x' => {
+            let hex = body.get(i+2..i+4)?;
+            iter.next(); iter.next();
+            (u8::from_str_radix(hex, 16).ok()? as char, i + 4)
+          }
+          'u' => {
+            let close = body[i..].find('}')? + i;
+            let hex = body.get(i+3..close)?;
+            while iter.peek().map_or(false, |&(j, _)| j <= close) { iter.next(); }
+            (::std::char::from_u32(u32::from_str_radix(hex, 16).ok()?)?, close + 1)
+          }
+          _ => return None
+        }
+      }
+      else {
+        (c, i + c.len_utf8())
+      };
+    chars.push((c, start + i, start + end));
+  }
+  Some(chars)
+}
+
+/// Splits the characters of a class into intervals `(lo, hi, start, end)` as done by the parser: `a-z` is an interval and `-` is a character at the start or the end of the class.
+fn split_intervals(chars: &Vec<(char, usize, usize)>) -> Vec<(char, char, usize, usize)> {
+  let mut intervals = vec![];
+  let mut i = 0;
+  while i < chars.len() {
+    let (lo, start, end) = chars[i];
+    if i + 2 < chars.len() && chars[i+1].0 == '-' && !(i == 0 && lo == '-') {
+      let (hi, _, hi_end) = chars[i+2];
+      intervals.push((lo, hi, start, hi_end));
+      i += 3;
+    }
+    else {
+      intervals.push((lo, lo, start, end));
+      i += 1;
+    }
+  }
+  intervals
+}
+
+fn escape(c: char) -> String {
+  c.escape_default().collect()
+}
+
+fn display_interval(lo: char, hi: char) -> String {
+  if lo == hi { escape(lo) }
+  else { format!("{}-{}", escape(lo), escape(hi)) }
+}
+
+impl<'a, 'b, 'c> ExprByIndex for CharClassAnalysis<'a, 'b, 'c>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index)
+  }
+}
+
+impl<'a, 'b, 'c> Visitor<()> for CharClassAnalysis<'a, 'b, 'c>
+{
+  unit_visitor_impl!(str_literal);
+  unit_visitor_impl!(non_terminal);
+  unit_visitor_impl!(atom);
+  unit_visitor_impl!(sequence);
+  unit_visitor_impl!(choice);
+
+  fn visit_character_class(&mut self, this: usize, class: CharacterClassExpr) {
+    self.check_class(this, class);
+  }
+
+  fn visit_syntactic_predicate(&mut self, _this: usize, child: usize) {
+    let value_unused = self.value_unused;
+    self.value_unused = true;
+    self.visit_expr(child);
+    self.value_unused = value_unused;
+  }
+
+  fn visit_type_ascription(&mut self, _this: usize, child: usize, ty: IType) {
+    let value_unused = self.value_unused;
+    self.value_unused = match ty {
+      IType::Invisible
+    | IType::Regular(Type::Unit) => true,
+      _ => value_unused
+    };
+    self.visit_expr(child);
+    self.value_unused = value_unused;
+  }
+}
@@ -0,0 +1,122 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implements `#![derive_ast]`: the value of every rule defined by a sequence or a choice, and without semantic action or type ascription, is wrapped into a generated type named after the rule (`expr_list` gives `ExprList`).
+//!
+//! A sequence rule is wrapped into `AstNode(seq, Type, None)` which builds the tuple structure `Type(..)`. Each branch of a choice rule is wrapped into `AstNode(branch, Type, Some(Variant))` which builds the enumeration variant `Type::Variant(..)`. The variant is named after the rule called by the branch or its leading keyword (`"if" cond block` gives `If`), and `AltN` otherwise. The types themselves are generated after typing by `back::compiler::ast_types`.
+
+use middle::analysis::ast::*;
+use ast::Expression::*;
+use std::collections::HashMap;
+
+pub struct DeriveAst<'a, 'b: 'a>
+{
+  grammar: AGrammar<'a, 'b>,
+  /// The rule of every generated type.
+  types: HashMap<Ident, Ident>,
+  has_error: bool
+}
+
+impl<'a, 'b> DeriveAst<'a, 'b>
+{
+  pub fn rewrite(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if !grammar.attributes.derive_ast {
+      return Partial::Value(grammar);
+    }
+    let mut rewriter = DeriveAst {
+      grammar: grammar,
+      types: HashMap::new(),
+      has_error: false
+    };
+    for rule in rewriter.grammar.rules.clone() {
+      rewriter.rewrite_rule(rule);
+    }
+    if rewriter.has_error {
+      Partial::Nothing
+    }
+    else {
+      Partial::Value(rewriter.grammar)
+    }
+  }
+
+  fn rewrite_rule(&mut self, rule: Rule) {
+    let ty = self.camel_case(ident_to_string(rule.ident()));
+    match self.grammar.expr_by_index(rule.expr_idx) {
+      Sequence(_) => {
+        self.check_type_name(rule, ty);
+        self.wrap(rule.expr_idx, ty, None);
+      }
+      Choice(branches) => {
+        self.check_type_name(rule, ty);
+        let mut variants: Vec<Ident> = vec![];
+        for (i, branch) in branches.into_iter().enumerate() {
+          let alt = string_to_ident(self.grammar.cx, format!("Alt{}", i + 1));
+          let variant = match self.variant_hint(branch) {
+            Some(variant) if !variants.contains(&variant) => variant,
+            _ => alt
+          };
+          variants.push(variant);
+          self.wrap(branch, ty, Some(variant));
+        }
+      }
+      _ => ()
+    }
+  }
+
+  fn check_type_name(&mut self, rule: Rule, ty: Ident) {
+    let clash = self.grammar.generated_type_clash(ty).or_else(||
+      self.types.get(&ty).map(|other| format!("the type generated for the rule `{}`", other)));
+    match clash {
+      Some(clash) => {
+        self.grammar.span_err(rule.span(), format!(
+          "In rule {}: the type `{}` generated by `#![derive_ast]` clashes with {}.\n\
+           Help: rename the rule, or give its type with a semantic action.",
+          rule.ident(), ty, clash));
+        self.has_error = true;
+      }
+      None => {
+        self.types.insert(ty, rule.ident());
+      }
+    }
+  }
+
+  /// Replaces the expression `this` by `AstNode(this, ty, variant)`. The expression is moved to a new index so the parent expression does not need to be updated.
+  fn wrap(&mut self, this: usize, ty: Ident, variant: Option<Ident>) {
+    let span = self.grammar[this].span();
+    let expr = self.grammar.exprs[this].clone();
+    let moved = self.grammar.alloc_expr(span, expr);
+    self.grammar.exprs[this] = AstNode(moved, ty, variant);
+  }
+
+  fn variant_hint(&self, branch: usize) -> Option<Ident> {
+    match self.grammar.expr_by_index(branch) {
+      NonTerminalSymbol(rule) => Some(self.camel_case(ident_to_string(rule))),
+      _ => self.keyword(branch)
+    }
+  }
+
+  /// The leading keyword of `expr`, if any. The sequences introduced by `#![skip]` are traversed.
+  fn keyword(&self, expr: usize) -> Option<Ident> {
+    match self.grammar.expr_by_index(expr) {
+      StrLiteral(ref lit) if !lit.is_empty() && lit.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+        Some(self.camel_case(lit.clone())),
+      Sequence(seq) => self.keyword(seq[0]),
+      _ => None
+    }
+  }
+
+  fn camel_case(&self, name: String) -> Ident {
+    camel_case(self.grammar.cx, name)
+  }
+}
@@ -0,0 +1,209 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Computes the FIRST_k sets of the expressions: the sequences of the `k` first symbols they can read. The sets are approximated: the predicates are considered to read nothing, and a sequence shorter than `k` symbols means the expression can stop after it.
+
+use middle::analysis::ast::*;
+use rust;
+use std::collections::{HashMap, BTreeSet};
+use std::fmt::{Display, Formatter, Error};
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Symbol
+{
+  Char(char),
+  /// The printed class and its intervals.
+  Class(String, Vec<(char, char)>),
+  Any,
+  /// A token pattern or the `indent` primitive.
+  Other(String)
+}
+
+impl Symbol
+{
+  pub fn overlap(&self, other: &Symbol) -> bool {
+    use self::Symbol::*;
+    let class_contains = |intervals: &Vec<(char, char)>, c: char|
+      intervals.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+    match (self, other) {
+      (&Any, _) | (_, &Any) => true,
+      (&Char(c1), &Char(c2)) => c1 == c2,
+      (&Char(c), &Class(_, ref intervals))
+    | (&Class(_, ref intervals), &Char(c)) => class_contains(intervals, c),
+      (&Class(_, ref intervals1), &Class(_, ref intervals2)) =>
+        intervals1.iter().any(|&(lo1, hi1)|
+          intervals2.iter().any(|&(lo2, hi2)| lo1 <= hi2 && lo2 <= hi1)),
+      (&Other(ref s1), &Other(ref s2)) => s1 == s2,
+      _ => false
+    }
+  }
+}
+
+impl Display for Symbol
+{
+  fn fmt(&self, formatter: &mut Formatter) -> Result<(), Error> {
+    match self {
+      &Symbol::Char(c) => formatter.write_fmt(format_args!("{:?}", c)),
+      &Symbol::Class(ref class, _) => formatter.write_str(class),
+      &Symbol::Any => formatter.write_str("."),
+      &Symbol::Other(ref name) => formatter.write_str(name)
+    }
+  }
+}
+
+/// The sequences of at most `k` symbols an expression can start with. A sequence shorter than `k` means the expression can stop after it.
+pub type FirstSet = BTreeSet<Vec<Symbol>>;
+
+/// The FIRST_k sets of the rules of a grammar, from which the sets of the expressions are computed.
+pub struct FirstSets<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  k: usize,
+  rules_first: HashMap<Ident, FirstSet>
+}
+
+impl<'a, 'b, 'c> FirstSets<'a, 'b, 'c>
+{
+  /// Computes the FIRST_k set of every rule until a fixpoint is reached, the sets can only grow.
+  pub fn new(grammar: &'c AGrammar<'a, 'b>, k: usize) -> Self {
+    let mut first_sets = FirstSets {
+      grammar: grammar,
+      k: k,
+      rules_first: grammar.rules.iter()
+        .map(|rule| (rule.ident(), FirstSet::new()))
+        .collect()
+    };
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for rule in &grammar.rules {
+        let first = first_sets.of_expr(rule.expr_idx);
+        let old = first_sets.rules_first.get_mut(&rule.ident()).unwrap();
+        if !first.is_subset(old) {
+          old.extend(first);
+          changed = true;
+        }
+      }
+    }
+    first_sets
+  }
+
+  pub fn of_rule(&self, rule: Ident) -> &FirstSet {
+    &self.rules_first[&rule]
+  }
+
+  pub fn of_expr(&self, expr: usize) -> FirstSet {
+    use middle::analysis::ast::Expression::*;
+    let k = self.k;
+    let symbols = |symbols: Vec<Symbol>| -> FirstSet {
+      Some(symbols.into_iter().take(k).collect()).into_iter().collect()
+    };
+    match self.grammar.exprs[expr] {
+      StrLiteral(ref lit) => symbols(lit.chars().map(Symbol::Char).collect()),
+      ByteLiteral(ref bytes) => symbols(bytes.iter().map(|&b| Symbol::Char(b as char)).collect()),
+      TokenPattern(ref pat) => symbols(vec![Symbol::Other(rust::pat_to_string(pat))]),
+      AnySingleChar => symbols(vec![Symbol::Any]),
+      CharacterClass(ref class) => symbols(vec![Symbol::Class(class.to_string(),
+        class.intervals.iter().map(|interval| (interval.lo, interval.hi)).collect())]),
+      Indentation(IndentKind::Indent) => symbols(vec![Symbol::Other(format!("indent"))]),
+      Indentation(_)
+    | NotPredicate(_)
+    | AndPredicate(_) => symbols(vec![]),
+      NonTerminalSymbol(rule) => self.rules_first.get(&rule).cloned().unwrap_or_default(),
+      Sequence(ref children) => self.of_sequence(children),
+      Choice(ref children) => children.iter()
+        .flat_map(|&child| self.of_expr(child))
+        .collect(),
+      ZeroOrMore(child)
+    | CountedRepeat(child, _) => star(self.of_expr(child), k),
+      OneOrMore(child) => {
+        let first = self.of_expr(child);
+        concat(first.clone(), star(first, k), k)
+      }
+      ZeroOrOne(child) => {
+        let mut first = self.of_expr(child);
+        first.insert(vec![]);
+        first
+      }
+      SemanticAction(child, _)
+    | StructAction(child, _, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child)
+    | Binding(_, child)
+    | Capture(child)
+    | AstNode(child, _, _) => self.of_expr(child),
+      Precedence(atom, ref levels) => {
+        let prefixes: FirstSet = levels.iter()
+          .filter(|level| level.assoc == Associativity::Prefix)
+          .flat_map(|level| self.of_expr(level.op))
+          .collect();
+        let operand = concat(star(prefixes, k), self.of_expr(atom), k);
+        let operators: FirstSet = levels.iter()
+          .filter(|level| level.assoc != Associativity::Prefix)
+          .flat_map(|level| {
+            let op = self.of_expr(level.op);
+            if level.assoc == Associativity::Postfix { op }
+            else { concat(op, operand.clone(), k) }
+          })
+          .collect();
+        concat(operand.clone(), star(operators, k), k)
+      }
+    }
+  }
+
+  /// The FIRST_k set of the sequence of expressions `exprs`.
+  pub fn of_sequence(&self, exprs: &[usize]) -> FirstSet {
+    let empty: FirstSet = Some(vec![]).into_iter().collect();
+    exprs.iter().fold(empty, |first, &expr| concat(first, self.of_expr(expr), self.k))
+  }
+}
+
+pub fn concat(first: FirstSet, next: FirstSet, k: usize) -> FirstSet {
+  let mut result = FirstSet::new();
+  for seq in first {
+    if seq.len() >= k {
+      result.insert(seq);
+    }
+    else {
+      for next_seq in &next {
+        let mut seq = seq.clone();
+        seq.extend(next_seq.iter().take(k - seq.len()).cloned());
+        result.insert(seq);
+      }
+    }
+  }
+  result
+}
+
+pub fn star(first: FirstSet, k: usize) -> FirstSet {
+  let mut result: FirstSet = Some(vec![]).into_iter().collect();
+  loop {
+    let mut next = concat(result.clone(), first.clone(), k);
+    next.extend(result.iter().cloned());
+    if next == result {
+      return result;
+    }
+    result = next;
+  }
+}
+
+/// Two sequences conflict if their symbols overlap up to the end of the shortest one.
+pub fn conflict(seq1: &Vec<Symbol>, seq2: &Vec<Symbol>) -> bool {
+  seq1.iter().zip(seq2.iter()).all(|(s1, s2)| s1.overlap(s2))
+}
+
+/// `true` if a sequence of `first1` conflicts with a sequence of `first2`.
+pub fn overlap(first1: &FirstSet, first2: &FirstSet) -> bool {
+  first1.iter().any(|seq1| first2.iter().any(|seq2| conflict(seq1, seq2)))
+}
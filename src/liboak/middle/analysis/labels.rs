@@ -0,0 +1,126 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks the labels of a rule (e.g. `name:identifier "=" value:expr > Binding { name, value }`). When labels are bound, the action is called by name, so every parameter of the action (or field of the structure) must be bound by a label. A label that is never used by an action or a counted repetition is reported with a warning.
+
+use middle::analysis::ast::*;
+
+pub struct LabelAnalysis<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  rule: Rule,
+  bound: Vec<Ident>,
+  used: Vec<Ident>,
+  has_error: bool
+}
+
+impl<'a, 'b, 'c> LabelAnalysis<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if LabelAnalysis::has_error(&grammar) {
+      Partial::Nothing
+    } else {
+      Partial::Value(grammar)
+    }
+  }
+
+  fn has_error(grammar: &'c AGrammar<'a, 'b>) -> bool {
+    let mut has_error = false;
+    for rule in &grammar.rules {
+      let mut analyser = LabelAnalysis {
+        grammar: grammar,
+        rule: rule.clone(),
+        bound: vec![],
+        used: vec![],
+        has_error: false
+      };
+      analyser.visit_expr(rule.expr_idx);
+      analyser.warn_unused_labels();
+      has_error |= analyser.has_error;
+    }
+    has_error
+  }
+
+  fn warn_unused_labels(&self) {
+    for label in &self.bound {
+      if !self.used.contains(label) {
+        self.grammar.span_warn(self.rule.span(), format!(
+          "In rule {}: The label `{}` is never used. Labels are passed by name to the action \
+           of the sequence or used as the count of a repetition (e.g. `e{{{}}}`).",
+          self.rule.ident(), label, label));
+      }
+    }
+  }
+
+  fn check_named_args(&mut self, child: usize, names: Vec<Ident>, target: String) {
+    let labels = self.grammar.bound_labels(child);
+    for name in names {
+      if !labels.contains(&name) {
+        self.grammar.span_err(self.rule.span(), format!(
+          "In rule {}: {} expects the label `{}` but it is not bound in the sequence. \
+           Add a label such as in `{}:expr`.",
+          self.rule.ident(), target, name, name));
+        self.has_error = true;
+      }
+      self.used.push(name);
+    }
+  }
+}
+
+impl<'a, 'b, 'c> ExprByIndex for LabelAnalysis<'a, 'b, 'c>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index)
+  }
+}
+
+impl<'a, 'b, 'c> Visitor<()> for LabelAnalysis<'a, 'b, 'c>
+{
+  unit_visitor_impl!(str_literal);
+  unit_visitor_impl!(atom);
+  unit_visitor_impl!(sequence);
+  unit_visitor_impl!(choice);
+  unit_visitor_impl!(non_terminal);
+
+  fn visit_binding(&mut self, _this: usize, label: Ident, child: usize) {
+    self.bound.push(label);
+    self.visit_expr(child);
+  }
+
+  fn visit_counted_repeat(&mut self, _this: usize, child: usize, count: Ident) {
+    self.used.push(count);
+    self.visit_expr(child);
+  }
+
+  fn visit_semantic_action(&mut self, _this: usize, child: usize, action: Ident) {
+    if !self.grammar.bound_labels(child).is_empty() {
+      let params = self.grammar.action_params(action);
+      if params.iter().any(|param| param.is_none()) {
+        self.grammar.span_err(self.rule.span(), format!(
+          "In rule {}: The action `{}` is called by name since its expression has labels, \
+           so its parameters must be simple identifiers.",
+          self.rule.ident(), action));
+        self.has_error = true;
+      }
+      let names = params.into_iter().filter_map(|param| param).collect();
+      self.check_named_args(child, names, format!("The action `{}`", action));
+    }
+    self.visit_expr(child);
+  }
+
+  fn visit_struct_action(&mut self, _this: usize, child: usize, name: Ident, fields: Vec<Ident>) {
+    self.check_named_args(child, fields, format!("The structure `{}`", name));
+    self.visit_expr(child);
+  }
+}
@@ -0,0 +1,220 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reports the consecutive alternatives of a choice starting with the same expressions, such as `"a" b / "a" c`, and proposes the factored form `"a" (b / c)` as a suggestion that tools can apply.
+//!
+//! The prefix is parsed again each time an alternative fails after it. If it can consume a long input (it contains a rule or a repetition) and an alternative can fail after it, the parsing time can grow exponentially when the choice is nested: this is reported by the lint `backtracking`, and by the lint `left_factoring` otherwise.
+//!
+//! Factoring the prefix does not change the type of the choice when the alternatives are plain sequences. If an alternative has a semantic action or a type annotation, the values given to the action would change: the suggestion is not given and the user is told to adapt the actions.
+
+use middle::analysis::ast::*;
+use middle::analysis::well_formedness::*;
+use std::collections::HashMap;
+use std::cmp::min;
+
+pub struct LeftFactoring<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  exprs_wfa: &'c HashMap<usize, WFA>
+}
+
+impl<'a, 'b, 'c> LeftFactoring<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>, exprs_wfa: &HashMap<usize, WFA>) -> Partial<AGrammar<'a, 'b>> {
+    if LeftFactoring::check_left_factoring(&grammar, exprs_wfa) {
+      Partial::Nothing
+    } else {
+      Partial::Value(grammar)
+    }
+  }
+
+  /// Returns `true` if a denied lint is raised.
+  fn check_left_factoring(grammar: &'c AGrammar<'a, 'b>, exprs_wfa: &'c HashMap<usize, WFA>) -> bool {
+    let analyser = LeftFactoring {
+      grammar: grammar,
+      exprs_wfa: exprs_wfa
+    };
+    let mut denied = false;
+    for (expr_idx, expr) in grammar.exprs.iter().enumerate() {
+      if let &Expression::Choice(ref children) = expr {
+        denied |= analyser.check_choice(expr_idx, children);
+      }
+    }
+    denied
+  }
+
+  /// Groups the consecutive alternatives sharing a prefix. Only consecutive alternatives are factored since moving an alternative before another one can change the language.
+  fn check_choice(&self, this: usize, children: &Vec<usize>) -> bool {
+    let alternatives: Vec<_> = children.iter().map(|&child| self.grammar.sequence_elements(child)).collect();
+    let mut denied = false;
+    let mut first = 0;
+    while first < children.len() {
+      let mut last = first;
+      let mut prefix_len = 0;
+      for next in first+1..children.len() {
+        let common = self.common_prefix(&alternatives[first], &alternatives[next]);
+        let common = if next == first + 1 { common } else { min(common, prefix_len) };
+        if common == 0 { break; }
+        prefix_len = common;
+        last = next;
+      }
+      if last > first {
+        denied |= self.report_group(this, &children[first..last+1],
+          &alternatives[first..last+1], prefix_len);
+      }
+      first = last + 1;
+    }
+    denied
+  }
+
+  fn common_prefix(&self, alt1: &Vec<usize>, alt2: &Vec<usize>) -> usize {
+    alt1.iter().zip(alt2.iter())
+      .take_while(|&(&e1, &e2)| self.grammar.expr_eq(self.grammar.parsed_expr(e1), self.grammar.parsed_expr(e2)))
+      .count()
+  }
+
+  fn report_group(&self, this: usize, children: &[usize], alternatives: &[Vec<usize>],
+    prefix_len: usize) -> bool
+  {
+    // A non-last alternative fully included in the prefix hides the next ones, they are reported as unreachable or duplicated.
+    if alternatives[..alternatives.len()-1].iter().any(|alt| self.without_skip(&alt[prefix_len..]).is_empty()) {
+      return false;
+    }
+    let prefix = &alternatives[0][..prefix_len];
+    let costly = prefix.iter().any(|&e| self.grammar.may_consume_long_input(e))
+      && alternatives[..alternatives.len()-1].iter()
+           .any(|alt| alt[prefix_len..].iter().any(|&e| self.can_fail(e)));
+    let (lint, msg) =
+      if costly {
+        (Lint::Backtracking, format!(
+          "These alternatives start with the same expressions: they are parsed again each time \
+           a previous alternative fails after them, and the parsing time can grow exponentially \
+           if the choice is nested.\n\
+           Help: left-factor the common prefix, or memoize the rule."))
+      }
+      else {
+        (Lint::LeftFactoring, format!(
+          "These alternatives start with the same expressions, they are parsed again each time \
+           a previous alternative fails after them.\n\
+           Help: left-factor the common prefix."))
+      };
+    let first = children[0];
+    let last = children[children.len()-1];
+    let mut messages = vec![
+      (self.grammar[children[1]].span(), msg),
+      (self.grammar[first].span(), format!("The prefix is first parsed in this alternative."))
+    ];
+    let suggestion =
+      if self.has_values(children, alternatives, prefix_len) {
+        messages.push((self.grammar[first].span(), format!(
+          "The alternatives build their values with semantic actions or type annotations: \
+           factoring the prefix changes the values given to the actions, they must be adapted \
+           by hand.")));
+        None
+      }
+      else {
+        self.factored_form(alternatives, prefix_len).map(|replacement| {
+          let span = self.grammar[first].span().to(self.grammar[last].span());
+          (span, format!("factor the common prefix"), replacement)
+        })
+      };
+    self.grammar.lint_with_suggestion(lint, self.grammar.rule_of_expr(this), messages, suggestion)
+  }
+
+  /// `true` if the alternatives are not plain sequences or if their prefixes build different values (e.g. `(a > f) b / (a > g) c`).
+  fn has_values(&self, children: &[usize], alternatives: &[Vec<usize>], prefix_len: usize) -> bool {
+    let first_prefix = &alternatives[0][..prefix_len];
+    children.iter().any(|&child| self.grammar.parsed_expr(child) != child)
+    || alternatives.iter().any(|alt| alt[..prefix_len].iter().zip(first_prefix.iter())
+         .any(|(&e1, &e2)| !self.grammar.expr_eq(e1, e2)))
+  }
+
+  /// Builds `prefix (rest1 / rest2)` from the source of the alternatives. An empty rest can only appear in the last alternative (the other cases are left to `report_group`), it is turned into an optional expression: `a b / a` is factored into `a b?`.
+  fn factored_form(&self, alternatives: &[Vec<usize>], prefix_len: usize) -> Option<String> {
+    let prefix = self.source(&alternatives[0][..prefix_len])?;
+    let mut rests = vec![];
+    let mut optional = false;
+    for alt in alternatives {
+      let rest = self.without_skip(&alt[prefix_len..]);
+      if rest.is_empty() {
+        optional = true;
+      }
+      else {
+        rests.push(rest);
+      }
+    }
+    let factored =
+      if rests.len() == 1 && rests[0].len() == 1 && self.is_atom(rests[0][0]) {
+        self.source(&rests[0])?
+      }
+      else {
+        let mut sources = vec![];
+        for rest in rests {
+          sources.push(self.source(&rest)?);
+        }
+        format!("({})", sources.join(" / "))
+      };
+    Some(format!("{} {}{}", prefix, factored, if optional { "?" } else { "" }))
+  }
+
+  /// The source code of the expressions of a sequence. The spans of the parenthesized expressions do not include the parenthesis so they are added back.
+  fn source(&self, elements: &[usize]) -> Option<String> {
+    let mut sources = vec![];
+    for e in self.without_skip(elements) {
+      let source = self.grammar.snippet(self.grammar[e].span())?;
+      let parenthesized = match self.grammar.exprs[e] {
+        Expression::Choice(_) => true,
+        Expression::Sequence(ref seq) => self.without_skip(seq).len() > 1,
+        _ => false
+      };
+      sources.push(if parenthesized { format!("({})", source) } else { source });
+    }
+    Some(sources.join(" "))
+  }
+
+  /// Removes the calls to the skip rule inserted by `SkipRewriting`, they are inserted again when the factored form is compiled.
+  fn without_skip(&self, elements: &[usize]) -> Vec<usize> {
+    elements.iter().cloned().filter(|&e| !self.is_skip(e)).collect()
+  }
+
+  fn is_atom(&self, expr: usize) -> bool {
+    match self.grammar.exprs[expr] {
+      Expression::StrLiteral(_)
+    | Expression::ByteLiteral(_)
+    | Expression::NonTerminalSymbol(_)
+    | Expression::CharacterClass(_)
+    | Expression::AnySingleChar => true,
+      Expression::Sequence(ref seq) => {
+        let seq = self.without_skip(seq);
+        seq.len() == 1 && self.is_atom(seq[0])
+      }
+      _ => false
+    }
+  }
+
+  fn is_skip(&self, expr: usize) -> bool {
+    let skip = self.grammar.attributes.skip.map(|skip| skip.node);
+    match self.grammar.exprs[expr] {
+      Expression::TypeAscription(child, IType::Invisible) => match self.grammar.exprs[child] {
+        Expression::NonTerminalSymbol(rule) => Some(rule) == skip,
+        _ => false
+      },
+      _ => false
+    }
+  }
+
+  fn can_fail(&self, expr: usize) -> bool {
+    self.exprs_wfa.get(&expr).map_or(true, |wfa| wfa.can_fail)
+  }
+}
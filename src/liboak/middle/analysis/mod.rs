@@ -18,29 +18,62 @@ use middle::analysis::duplicate::*;
 use middle::analysis::undeclared_rule::*;
 use middle::analysis::undeclared_action::*;
 use middle::analysis::well_formedness::*;
+use middle::analysis::labels::*;
 use middle::analysis::attribute::*;
+use middle::analysis::skip::*;
 use middle::analysis::useless_chaining::*;
 use middle::analysis::unreachable_rule::*;
+use middle::analysis::derive_ast::*;
+use middle::analysis::with_spans::*;
+use middle::analysis::unused::*;
+use middle::analysis::char_class::*;
+use middle::analysis::backtracking::*;
+use middle::analysis::left_factoring::*;
+use middle::analysis::structural_duplicate::*;
+use middle::analysis::stats::*;
 
 mod duplicate;
 mod undeclared_rule;
 mod undeclared_action;
 mod well_formedness;
+mod labels;
 mod attribute;
+mod skip;
 mod useless_chaining;
 mod unreachable_rule;
+mod derive_ast;
+mod with_spans;
+mod unused;
+mod char_class;
+mod backtracking;
+mod left_factoring;
+mod structural_duplicate;
+mod stats;
+mod first_set;
 pub mod ast;
 
 pub fn analyse<'a, 'b>(cx: &'a ExtCtxt<'b>, fgrammar: FGrammar) -> Partial<AGrammar<'a, 'b>> {
-  let grammar = AGrammar::new(cx, fgrammar.name, fgrammar.exprs, fgrammar.exprs_info);
+  let mut grammar = AGrammar::new(cx, fgrammar.name, fgrammar.exprs, fgrammar.exprs_info);
+  grammar.generics = fgrammar.generics;
   let frust_items = fgrammar.rust_items;
   let fattributes = fgrammar.attributes;
+  let frules_attributes = fgrammar.rules_attributes;
   rule_duplicate(grammar, fgrammar.rules)
   .and_then(|grammar| rust_functions_duplicate(grammar, frust_items))
+  .and_then(|grammar| decorate_with_attributes(grammar, fattributes, frules_attributes))
   .and_then(|grammar| UndeclaredRule::analyse(grammar))
+  .and_then(|grammar| UnusedItems::analyse(grammar))
   .and_then(|grammar| UndeclaredAction::analyse(grammar))
+  .and_then(|grammar| CharClassAnalysis::analyse(grammar))
+  .and_then(|grammar| SkipRewriting::rewrite(grammar))
+  .and_then(|grammar| WithSpans::rewrite(grammar))
   .and_then(|grammar| WellFormedness::analyse(grammar))
-  .and_then(|grammar| UselessChaining::analyse(grammar))
-  .and_then(|grammar| UnreachableRule::analyse(grammar))
-  .and_then(|grammar| decorate_with_attributes(grammar, fattributes))
+  .and_then(|(grammar, exprs_wfa)| LabelAnalysis::analyse(grammar)
+    .and_then(|grammar| UselessChaining::analyse(grammar))
+    .and_then(|grammar| UnreachableRule::analyse(grammar))
+    .and_then(|grammar| Backtracking::analyse(grammar))
+    .and_then(|grammar| LeftFactoring::analyse(grammar, &exprs_wfa))
+    .and_then(|grammar| StructuralDuplicate::analyse(grammar))
+    .and_then(|grammar| GrammarStats::report(grammar, &exprs_wfa)))
+  .and_then(|grammar| DeriveAst::rewrite(grammar))
 }
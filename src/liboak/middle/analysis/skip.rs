@@ -0,0 +1,122 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Inserts the rule declared with `#![skip(rule)]` after every string (or byte) literal and between the elements of a sequence. For example, with `#![skip(spacing)]`, the expression `"let" ident "=" expr` is rewritten into `"let" spacing ident spacing "=" spacing expr`.
+//!
+//! The skip rule is ascribed with `(^)` so it is invisible to the typing stage. The skip rule itself and the rules annotated with `#[atomic]` are not rewritten, we say they are _token rules_.
+
+use middle::analysis::ast::*;
+use ast::Expression::*;
+
+pub struct SkipRewriting<'a, 'b: 'a>
+{
+  grammar: AGrammar<'a, 'b>,
+  skip: Ident
+}
+
+impl<'a, 'b> SkipRewriting<'a, 'b>
+{
+  pub fn rewrite(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    let skip = match grammar.attributes.skip {
+      None => return Partial::Value(grammar),
+      Some(skip) => skip
+    };
+    if !grammar.rules.iter().any(|rule| rule.ident() == skip.node) {
+      grammar.span_err(skip.span, format!(
+        "Undeclared skip rule `{}`.", skip.node));
+      return Partial::Nothing;
+    }
+    let mut rewriter = SkipRewriting {
+      grammar: grammar,
+      skip: skip.node
+    };
+    let rules = rewriter.grammar.rules.clone();
+    for rule in rules {
+      if !rewriter.is_token_rule(rule.ident()) {
+        rewriter.visit_expr(rule.expr_idx);
+      }
+    }
+    Partial::Value(rewriter.grammar)
+  }
+
+  fn is_token_rule(&self, rule: Ident) -> bool {
+    rule == self.skip || self.grammar.attributes.is_atomic(rule)
+  }
+
+  fn alloc_skip(&mut self, span: Span) -> usize {
+    let skip = self.grammar.alloc_expr(span, NonTerminalSymbol(self.skip));
+    self.grammar.alloc_expr(span, TypeAscription(skip, IType::Invisible))
+  }
+
+  /// Replaces the expression `this` by `this skip`. The expression is moved to a new index so the parent expression does not need to be updated.
+  fn append_skip(&mut self, this: usize) {
+    let span = self.grammar[this].span();
+    let expr = self.grammar.exprs[this].clone();
+    let moved = self.grammar.alloc_expr(span, expr);
+    let skip = self.alloc_skip(span);
+    self.grammar.exprs[this] = Sequence(vec![moved, skip]);
+  }
+
+  /// Literals are already followed by the skip rule.
+  fn ends_with_skip(expr: &Expression) -> bool {
+    match expr {
+      &StrLiteral(ref lit) => !lit.is_empty(),
+      &ByteLiteral(ref bytes) => !bytes.is_empty(),
+      _ => false
+    }
+  }
+}
+
+impl<'a, 'b> ExprByIndex for SkipRewriting<'a, 'b>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index).clone()
+  }
+}
+
+impl<'a, 'b> Visitor<()> for SkipRewriting<'a, 'b>
+{
+  unit_visitor_impl!(non_terminal);
+  unit_visitor_impl!(atom);
+  unit_visitor_impl!(choice);
+
+  fn visit_str_literal(&mut self, this: usize, lit: String) {
+    if !lit.is_empty() {
+      self.append_skip(this);
+    }
+  }
+
+  fn visit_byte_literal(&mut self, this: usize, bytes: Vec<u8>) {
+    if !bytes.is_empty() {
+      self.append_skip(this);
+    }
+  }
+
+  fn visit_sequence(&mut self, this: usize, children: Vec<usize>) {
+    let originals: Vec<_> = children.iter()
+      .map(|&child| self.expr_by_index(child))
+      .collect();
+    walk_exprs(self, children.clone());
+    let mut seq = vec![];
+    for (i, child) in children.into_iter().enumerate() {
+      if i > 0 && !SkipRewriting::ends_with_skip(&originals[i-1]) {
+        let span = self.grammar[child].span();
+        let skip = self.alloc_skip(span);
+        seq.push(skip);
+      }
+      seq.push(child);
+    }
+    self.grammar.exprs[this] = Sequence(seq);
+  }
+}
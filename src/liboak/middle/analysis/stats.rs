@@ -0,0 +1,134 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `#![grammar_stats]` prints, for every rule, whether it is nullable, its FIRST set, the lookahead needed to decide its choices, its recursion and its number of expressions.
+//!
+//! The lookahead of a choice is the smallest `k` such that the FIRST_k sets of its alternatives, the sequences of the `k` first symbols they can read, do not overlap: the choice is then decided without backtracking by reading `k` symbols. A rule is LL(k) if all the choices it contains are, the choices of the called rules are counted in these rules. The FIRST sets are approximated: the predicates are considered to read nothing, and an alternative shorter than `k` symbols overlaps any alternative it is a prefix of since the symbols following it are unknown.
+
+use middle::analysis::ast::*;
+use middle::analysis::well_formedness::*;
+use middle::analysis::first_set::*;
+use std::collections::{HashMap, VecDeque};
+
+/// The lookahead is not computed beyond this number of symbols.
+const MAX_K: usize = 3;
+
+pub struct GrammarStats<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  /// `first_sets[k-1]` are the FIRST_k sets.
+  first_sets: Vec<FirstSets<'a, 'b, 'c>>
+}
+
+impl<'a, 'b, 'c> GrammarStats<'a, 'b, 'c>
+{
+  pub fn report(grammar: AGrammar<'a, 'b>, exprs_wfa: &HashMap<usize, WFA>) -> Partial<AGrammar<'a, 'b>> {
+    if grammar.attributes.grammar_stats {
+      GrammarStats::print_stats(&grammar, exprs_wfa);
+    }
+    Partial::Value(grammar)
+  }
+
+  fn print_stats(grammar: &'c AGrammar<'a, 'b>, exprs_wfa: &HashMap<usize, WFA>) {
+    let stats = GrammarStats {
+      grammar: grammar,
+      first_sets: (1..MAX_K+1).map(|k| FirstSets::new(grammar, k)).collect()
+    };
+    println!("Statistics of the grammar `{}`:", grammar.name);
+    for rule in &grammar.rules {
+      let ident = rule.ident();
+      let wfa = exprs_wfa[&rule.expr_idx];
+      println!("\nrule {}", ident);
+      println!("  nullable: {}, can fail: {}",
+        if wfa.always_consume { "no (always consumes input)" } else { "yes" },
+        if wfa.can_fail { "yes" } else { "no" });
+      println!("  FIRST: {}", stats.display_first(ident));
+      println!("  lookahead: {}", match stats.lookahead(rule.expr_idx) {
+        Some(0) => format!("LL(0), no choice to decide"),
+        Some(k) => format!("LL({})", k),
+        None => format!("not LL({}), a choice may backtrack", MAX_K)
+      });
+      println!("  recursion: {}", match stats.recursion(ident) {
+        Some(1) => format!("direct"),
+        Some(n) => format!("through {} rules", n),
+        None => format!("none")
+      });
+      println!("  expressions: {}", stats.expressions_of(rule.expr_idx).len());
+    }
+  }
+
+  fn display_first(&self, rule: Ident) -> String {
+    let symbols: Vec<_> = self.first_sets[0].of_rule(rule).iter()
+      .map(|seq| seq.first().map_or(format!("ε"), |symbol| symbol.to_string()))
+      .collect();
+    symbols.join(", ")
+  }
+
+  /// The largest lookahead needed by the choices of the expression `expr`, `None` if one of them needs more than `MAX_K` symbols.
+  fn lookahead(&self, expr: usize) -> Option<usize> {
+    let mut max_k = 0;
+    for e in self.expressions_of(expr) {
+      if let Expression::Choice(ref children) = self.grammar.exprs[e] {
+        let k = (1..MAX_K+1).find(|&k| self.is_deterministic(children, k))?;
+        max_k = max_k.max(k);
+      }
+    }
+    Some(max_k)
+  }
+
+  fn is_deterministic(&self, children: &Vec<usize>, k: usize) -> bool {
+    let firsts: Vec<_> = children.iter()
+      .map(|&child| self.first_sets[k-1].of_expr(child))
+      .collect();
+    firsts.iter().enumerate().all(|(i, first1)|
+      firsts[i+1..].iter().all(|first2| !overlap(first1, first2)))
+  }
+
+  /// The length of the shortest cycle of calls from `rule` to itself.
+  fn recursion(&self, rule: Ident) -> Option<usize> {
+    let mut visited = vec![];
+    let mut to_visit: VecDeque<_> = self.calls(rule).into_iter().map(|callee| (callee, 1)).collect();
+    while let Some((callee, depth)) = to_visit.pop_front() {
+      if callee == rule {
+        return Some(depth);
+      }
+      if !visited.contains(&callee) {
+        visited.push(callee);
+        to_visit.extend(self.calls(callee).into_iter().map(|next| (next, depth + 1)));
+      }
+    }
+    None
+  }
+
+  fn calls(&self, rule: Ident) -> Vec<Ident> {
+    let expr = self.grammar.expr_index_of_rule(rule);
+    self.expressions_of(expr).into_iter()
+      .filter_map(|e| match self.grammar.exprs[e] {
+        Expression::NonTerminalSymbol(callee) => Some(callee),
+        _ => None
+      })
+      .collect()
+  }
+
+  /// The expression `expr` and all its sub-expressions.
+  fn expressions_of(&self, expr: usize) -> Vec<usize> {
+    let mut exprs = vec![];
+    let mut to_visit = vec![expr];
+    while let Some(e) = to_visit.pop() {
+      exprs.push(e);
+      to_visit.extend(self.grammar.exprs[e].children());
+    }
+    exprs
+  }
+}
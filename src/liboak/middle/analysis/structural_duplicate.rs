@@ -0,0 +1,170 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reports the rules with structurally identical bodies and the alternatives repeated in a choice. `duplicate` only checks the names of the rules.
+//!
+//! Two expressions are structurally equivalent if they are equal once their semantic actions, type ascriptions and labels are removed, and if the rules they call are equivalent. The equivalence of the rules is computed by partition refinement: the rules start in a single class which is split according to the shape of their bodies until it does not change, so `a = "x" a / "y"` and `b = "x" b / "y"` are equivalent.
+
+use middle::analysis::ast::*;
+use middle::analysis::unreachable_rule::UnreachableRule;
+use rust;
+use std::collections::HashMap;
+
+pub struct StructuralDuplicate<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  /// The equivalence class of every rule.
+  classes: HashMap<Ident, usize>
+}
+
+impl<'a, 'b, 'c> StructuralDuplicate<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if StructuralDuplicate::check_duplicates(&grammar) {
+      Partial::Nothing
+    } else {
+      Partial::Value(grammar)
+    }
+  }
+
+  /// Returns `true` if a denied lint is raised.
+  fn check_duplicates(grammar: &'c AGrammar<'a, 'b>) -> bool {
+    let mut analyser = StructuralDuplicate {
+      grammar: grammar,
+      classes: grammar.rules.iter().map(|rule| (rule.ident(), 0)).collect()
+    };
+    analyser.refine_classes();
+    let denied_rules = analyser.check_rules();
+    let mut denied_alternatives = false;
+    for (expr_idx, expr) in grammar.exprs.iter().enumerate() {
+      if let &Expression::Choice(ref children) = expr {
+        denied_alternatives |= analyser.check_choice(expr_idx, children);
+      }
+    }
+    denied_rules || denied_alternatives
+  }
+
+  /// Splits the classes of rules according to the shape of their bodies until a fixpoint is reached.
+  fn refine_classes(&mut self) {
+    let mut num_classes = 1;
+    loop {
+      let mut new_classes = HashMap::new();
+      let mut keys = HashMap::new();
+      for rule in &self.grammar.rules {
+        let key = (self.classes[&rule.ident()], self.shape(rule.expr_idx));
+        let next_class = keys.len();
+        let class = *keys.entry(key).or_insert(next_class);
+        new_classes.insert(rule.ident(), class);
+      }
+      self.classes = new_classes;
+      if keys.len() == num_classes {
+        break;
+      }
+      num_classes = keys.len();
+    }
+  }
+
+  fn check_rules(&self) -> bool {
+    let mut denied = false;
+    let mut first_of_class: HashMap<usize, &Rule> = HashMap::new();
+    for rule in &self.grammar.rules {
+      let class = self.classes[&rule.ident()];
+      match first_of_class.get(&class) {
+        Some(first) => {
+          let relation =
+            if self.grammar.expr_eq(first.expr_idx, rule.expr_idx) {
+              format!("is identical to the rule `{}`", first.ident())
+            }
+            else {
+              format!("parses the same input as the rule `{}`, up to the semantic actions \
+                and the names of the called rules", first.ident())
+            };
+          denied |= self.grammar.lint(Lint::DuplicateRule, Some(rule.ident()), vec![
+            (rule.span(), format!(
+              "In rule {}: this rule {}.\n\
+               Help: merge them by keeping one of the rules and calling it instead of the other.",
+              rule.ident(), relation)),
+            (first.span(), format!("The rule `{}` is declared here.", first.ident()))
+          ]);
+          continue;
+        }
+        None => ()
+      }
+      first_of_class.insert(class, rule);
+    }
+    denied
+  }
+
+  /// The alternatives already reported by the lint `unreachable_alternative` are not reported again.
+  fn check_choice(&self, this: usize, children: &Vec<usize>) -> bool {
+    let mut denied = false;
+    let rule = self.grammar.rule_of_expr(this);
+    let unreachable_reported = self.grammar.attributes.lint_level(Lint::UnreachableAlternative, rule) != LintLevel::Allow;
+    for (j, &later) in children.iter().enumerate().skip(1) {
+      if unreachable_reported && UnreachableRule::shadowing_alternative(self.grammar, children, j).is_some() {
+        continue;
+      }
+      let later_shape = self.shape(later);
+      if let Some(&earlier) = children[..j].iter().find(|&&earlier| self.shape(earlier) == later_shape) {
+        denied |= self.grammar.lint(Lint::DuplicateAlternative, rule, vec![
+          (self.grammar[later].span(), format!(
+            "This alternative parses the same input as a previous one: it is never chosen since the \
+             previous one succeeds whenever it would.\n\
+             Help: remove it, or merge its semantic action with the one of the previous alternative.")),
+          (self.grammar[earlier].span(), format!("The previous alternative."))
+        ]);
+      }
+    }
+    denied
+  }
+
+  /// A textual representation of `expr` without its semantic actions, type ascriptions and labels, where the called rules are replaced by their equivalence classes.
+  fn shape(&self, expr: usize) -> String {
+    use middle::analysis::ast::Expression::*;
+    let shapes = |exprs: &Vec<usize>, sep: &str| -> String {
+      let shapes: Vec<_> = exprs.iter().map(|&e| self.shape(e)).collect();
+      format!("({})", shapes.join(sep))
+    };
+    match self.grammar.exprs[expr] {
+      StrLiteral(ref lit) => format!("{:?}", lit),
+      ByteLiteral(ref bytes) => format!("b{:?}", bytes),
+      TokenPattern(ref pat) => format!("pat({})", rust::pat_to_string(pat)),
+      AnySingleChar => format!("."),
+      CharacterClass(ref class) => class.to_string(),
+      NonTerminalSymbol(rule) => format!("#{}", self.classes.get(&rule).cloned().unwrap_or(0)),
+      Sequence(ref children) => shapes(children, " "),
+      Choice(ref children) => shapes(children, " / "),
+      ZeroOrMore(child) => format!("{}*", self.shape(child)),
+      OneOrMore(child) => format!("{}+", self.shape(child)),
+      ZeroOrOne(child) => format!("{}?", self.shape(child)),
+      CountedRepeat(child, count) => format!("{}{{{}}}", self.shape(child), count),
+      NotPredicate(child) => format!("!{}", self.shape(child)),
+      AndPredicate(child) => format!("&{}", self.shape(child)),
+      SemanticAction(child, _)
+    | StructAction(child, _, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child)
+    | Binding(_, child)
+    | Capture(child)
+    | AstNode(child, _, _) => self.shape(child),
+      Precedence(atom, ref levels) => {
+        let levels: Vec<_> = levels.iter()
+          .map(|level| format!("{:?} {}", level.assoc, self.shape(level.op)))
+          .collect();
+        format!("precedence {} {{{}}}", self.shape(atom), levels.join(", "))
+      }
+      Indentation(kind) => format!("{:?}", kind)
+    }
+  }
+}
@@ -40,6 +40,16 @@ impl<'a, 'b, 'c> UndeclaredAction<'a, 'b, 'c>
     }
     analyser.has_undeclared
   }
+
+  fn check_action(&mut self, this: usize, action: Ident) {
+    if !self.grammar.rust_functions.contains_key(&action) {
+      self.grammar.expr_err(
+        this,
+        format!("Undeclared action `{}`. Function must be declared in the grammar scope.", action)
+      );
+      self.has_undeclared = true;
+    }
+  }
 }
 
 impl<'a, 'b, 'c> ExprByIndex for UndeclaredAction<'a, 'b, 'c>
@@ -58,12 +68,14 @@ impl<'a, 'b, 'c> Visitor<()> for UndeclaredAction<'a, 'b, 'c>
   unit_visitor_impl!(non_terminal);
 
   fn visit_semantic_action(&mut self, this: usize, _child: usize, action: Ident) {
-    if !self.grammar.rust_functions.contains_key(&action) {
-      self.grammar.expr_err(
-        this,
-        format!("Undeclared action `{}`. Function must be declared in the grammar scope.", action)
-      );
-      self.has_undeclared = true;
+    self.check_action(this, action);
+  }
+
+  fn visit_precedence(&mut self, this: usize, atom: usize, levels: Vec<PrecedenceLevel>) {
+    for level in levels {
+      self.check_action(this, level.action);
+      self.visit_expr(level.op);
     }
+    self.visit_expr(atom);
   }
 }
@@ -12,282 +12,365 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#![macro_use]
-use std::char::*;
+//! Detects the alternatives of a choice that can never succeed because an earlier alternative always succeeds on their input. For example, in `identifier / "let"`, `identifier` matches `let` so the second alternative is never tried.
+//!
+//! For a later alternative `e2`, we compute a set of prefixes covering every input on which `e2` succeeds, where each position of a prefix is a set of characters (`["a-z"] "b"` gives the prefix `[a-z] b`). The earlier alternative `e1` is then interpreted on each prefix: a character read outside of the prefix, or from a set of characters only partly accepted, gives an unknown outcome. If `e1` succeeds on every prefix, whatever comes next in the input, it shadows `e2`.
+//!
+//! The analysis is conservative: when the prefixes of `e2` are too numerous or `e1` is too deep, nothing is reported.
+
 use middle::analysis::ast::*;
-use self::Pattern::*;
-use self::Character::*;
-
-#[derive(Copy, Clone)]
-enum Pattern {
-  // OneOrMore, // +
-  // ZeroOrMore, // *
-  // ZeroOrOne, // ?
-  One,
-  // And, // &
-  // Not, // !
-}
+use std::char;
 
-#[derive(Copy, Clone)]
-enum Character{
-  Char(char),
-  Any // .
-}
+/// Prefixes are cut after `MAX_PREFIX_LEN` characters.
+const MAX_PREFIX_LEN: usize = 8;
+/// Over `MAX_PREFIXES` prefixes, an alternative is approximated by the empty prefix which covers every input.
+const MAX_PREFIXES: usize = 64;
+/// Rules are inlined up to `MAX_RULE_DEPTH` calls.
+const MAX_RULE_DEPTH: usize = 8;
 
-struct Occurence
+/// A set of characters represented by sorted and disjoint intervals.
+#[derive(Clone)]
+struct CharSet
 {
-  choice: Vec<Vec<(Character,Pattern)>>
+  intervals: Vec<(char, char)>
 }
 
-impl Occurence
+impl CharSet
 {
-  fn push_sequence(&self, other: Occurence) -> Occurence{
-    let mut res = vec![];
-    for choice1 in other.choice.clone(){
-      for choice2 in self.choice.clone(){
-        let mut tmp = choice2.clone();
-        for couple in choice1.clone(){
-          tmp.push(couple)
+  fn new(mut intervals: Vec<(char, char)>) -> CharSet {
+    intervals.sort();
+    let mut merged: Vec<(char, char)> = vec![];
+    for (lo, hi) in intervals {
+      match merged.last_mut() {
+        Some(last) if lo as u32 <= last.1 as u32 + 1 => {
+          if hi > last.1 { last.1 = hi; }
+          continue;
         }
-        res.push(tmp)
+        _ => ()
       }
+      merged.push((lo, hi));
     }
-    Occurence{
-      choice: res
-    }
+    CharSet { intervals: merged }
   }
 
-  fn merge_choice(&self, other: Occurence) -> Occurence {
-    let mut res = self.choice.clone();
-    for choice in other.choice.clone(){
-      res.push(choice)
-    }
-    Occurence{
-      choice: res
-    }
+  fn one(c: char) -> CharSet {
+    CharSet::new(vec![(c, c)])
   }
 
-  fn copy(&self) -> Occurence{
-    Occurence{
-      choice: self.choice.to_vec()
-    }
+  fn any() -> CharSet {
+    CharSet::new(vec![('\u{0}', char::MAX)])
   }
 
-  fn is_unreachable_with(&self, target: Occurence) -> bool {
-    let mut res = true;
-    for seq in self.choice.clone(){
-      if !(target.success_with(seq)) {
-        res = false;
-        break;
-      }
-    }
-    res
+  fn class(class: &CharacterClassExpr) -> CharSet {
+    CharSet::new(class.intervals.iter().map(|i| (i.lo, i.hi)).collect())
   }
 
-  fn success_with(&self, seq2: Vec<(Character, Pattern)>) -> bool {
-    let mut res = false;
-    for seq1 in self.choice.clone(){
-      if self.succeed_before(seq1,seq2.to_vec()) {
-        res = true;
-      }
-    }
-    res
+  fn is_subset(&self, other: &CharSet) -> bool {
+    self.intervals.iter().all(|&(lo, hi)|
+      other.intervals.iter().any(|&(olo, ohi)| olo <= lo && hi <= ohi))
   }
 
-  fn succeed_before(&self, seq1: Vec<(Character, Pattern)>, seq2: Vec<(Character, Pattern)>) -> bool{
-    let mut res = true;
-    if seq2.len()<seq1.len() {
-      res=false;
-    }
-    else{
-      for (i,character_pattern1) in seq1.iter().enumerate(){
-        if let Some(character_pattern2) = seq2.get(i) {
-          let &(c1,_p1) = character_pattern1;
-          let &(c2,_p2) = character_pattern2;
-          match c1 {
-            Char(character1) => {
-              match c2 {
-                Char(character2) => {
-                  if character1!=character2 {
-                    res=false;
-                    break;
-                  }
-                }
-                Any => {
-                  res=false;
-                  break;
-                }
-              }
-            }
-            Any => { continue; }
-          }
-        }
-      }
-    }
-    res
+  fn is_disjoint(&self, other: &CharSet) -> bool {
+    self.intervals.iter().all(|&(lo, hi)|
+      other.intervals.iter().all(|&(olo, ohi)| hi < olo || ohi < lo))
   }
 }
 
-pub struct UnreachableRule<'a: 'c, 'b: 'a, 'c>
+/// A prefix of the inputs accepted by an expression. It is `complete` if the expression stops right after the prefix, otherwise the expression may consume more input.
+#[derive(Clone)]
+struct Prefix
 {
-  grammar: &'c AGrammar<'a, 'b>
+  chars: Vec<CharSet>,
+  complete: bool
 }
 
-impl <'a, 'b, 'c> UnreachableRule<'a, 'b, 'c>
+impl Prefix
 {
-  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
-    UnreachableRule::check_unreachable_rule(&grammar);
-    Partial::Value(grammar)
+  fn empty() -> Prefix {
+    Prefix { chars: vec![], complete: true }
   }
 
-  fn check_unreachable_rule(grammar: &'c AGrammar<'a, 'b>){
-    let mut analyser = UnreachableRule{
-      grammar: grammar
-    };
+  fn open() -> Prefix {
+    Prefix { chars: vec![], complete: false }
+  }
+
+  fn single(set: CharSet) -> Prefix {
+    Prefix { chars: vec![set], complete: true }
+  }
 
-    for rule in &grammar.rules {
-      analyser.visit_expr(rule.expr_idx);
+  fn opened(mut self) -> Prefix {
+    self.complete = false;
+    self
+  }
+
+  fn concat(&self, other: &Prefix) -> Prefix {
+    let mut chars = self.chars.clone();
+    chars.extend(other.chars.iter().cloned());
+    let mut complete = other.complete;
+    if chars.len() > MAX_PREFIX_LEN {
+      chars.truncate(MAX_PREFIX_LEN);
+      complete = false;
     }
+    Prefix { chars: chars, complete: complete }
   }
 }
 
-impl<'a, 'b, 'c> ExprByIndex for UnreachableRule<'a, 'b, 'c>
+/// The outcome of an expression on every input starting with a prefix. `SuccessAnywhere` is a success whose end position is unknown.
+#[derive(Clone, Copy)]
+enum Outcome
 {
-  fn expr_by_index(&self, index: usize) -> Expression {
-    self.grammar.expr_by_index(index).clone()
-  }
+  Fail,
+  Success(usize),
+  SuccessAnywhere,
+  Unknown
 }
 
-impl<'a, 'b, 'c> Visitor<Occurence> for UnreachableRule<'a, 'b, 'c>
+impl Outcome
 {
-  fn visit_choice(&mut self, _this: usize, children: Vec<usize>) -> Occurence{
-    let mut occurences_of_children = vec![];
-    for child in children.clone(){
-      let occ = self.visit_expr(child);
-      if !occ.choice.is_empty() {
-          occurences_of_children.push((occ,child));
-      }
-    }
-    for (i, cp1) in occurences_of_children.iter().enumerate(){
-      let (_left, right) = occurences_of_children.split_at(i+1);
-      let &(ref occ1, child1) = cp1;
-      for cp2 in right{
-        let &(ref occ2, child2) = cp2;
-        if occ2.is_unreachable_with(occ1.copy()) {
-          self.grammar.span_warn(
-            self.grammar[child2].span(),
-            format!("This alternative will nerver succeed.")
-          );
-          self.grammar.span_note(
-            self.grammar[child1].span(),
-            format!("Because this alternative succeeded before reaching the previous one.")
-          );
-        }
-      }
+  fn is_success(self) -> bool {
+    match self {
+      Outcome::Success(_) | Outcome::SuccessAnywhere => true,
+      _ => false
     }
+  }
+}
 
-    let mut res = vec![];
-    if let Some((first, rest)) = children.split_first(){
-      let mut occ = self.visit_expr(*first);
-      for child in rest{
-        occ = occ.merge_choice(self.visit_expr(*child))
-      }
-      res = occ.choice;
-    }
-    Occurence{
-      choice: res
+pub struct UnreachableRule<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>
+}
+
+impl<'a, 'b, 'c> UnreachableRule<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if UnreachableRule::check_unreachable_rule(&grammar) {
+      Partial::Nothing
+    } else {
+      Partial::Value(grammar)
     }
   }
 
-  fn visit_sequence(&mut self, _this: usize, children: Vec<usize>) -> Occurence{
-    let mut res = vec![];
-    if let Some((first, rest)) = children.split_first(){
-      let mut occ = self.visit_expr(*first);
-      for child in rest{
-        occ = occ.push_sequence(self.visit_expr(*child))
+  /// Returns `true` if a denied lint is raised.
+  fn check_unreachable_rule(grammar: &'c AGrammar<'a, 'b>) -> bool {
+    let analyser = UnreachableRule {
+      grammar: grammar
+    };
+    let mut denied = false;
+    for (expr_idx, expr) in grammar.exprs.iter().enumerate() {
+      if let &Expression::Choice(ref children) = expr {
+        denied |= analyser.check_choice(expr_idx, children);
       }
-      res = occ.choice;
-    }
-    Occurence{
-      choice: res
     }
+    denied
   }
 
-  fn visit_atom(&mut self, _this: usize) -> Occurence{
-    Occurence{
-      choice: vec![]
-    }
+  /// The alternative preceding `children[j]` that succeeds on all its input, if any. `StructuralDuplicate` uses it to not report these alternatives twice.
+  pub fn shadowing_alternative(grammar: &'c AGrammar<'a, 'b>, children: &Vec<usize>, j: usize) -> Option<usize> {
+    let analyser = UnreachableRule {
+      grammar: grammar
+    };
+    analyser.shadowing(children, j)
   }
 
-  fn visit_str_literal(&mut self, _this: usize, lit: String) -> Occurence{
-    let mut seq = vec![];
-    for c in lit.chars(){
-      seq.push((Char(c),One))
-    }
-    let mut res = vec![];
-    res.push(seq);
-    Occurence{
-      choice: res
-    }
+  fn shadowing(&self, children: &Vec<usize>, j: usize) -> Option<usize> {
+    let prefixes = self.prefixes(children[j], 0);
+    children[..j].iter().cloned()
+      .find(|&earlier| prefixes.iter().all(|prefix| self.run(earlier, 0, prefix, 0).is_success()))
   }
 
-  fn visit_any_single_char(&mut self, _this: usize) -> Occurence{
-    let mut seq = vec![];
-    seq.push((Any,One));
-    let mut res = vec![];
-    res.push(seq);
-    Occurence{
-      choice: res
+  fn check_choice(&self, this: usize, children: &Vec<usize>) -> bool {
+    let mut denied = false;
+    for (j, &later) in children.iter().enumerate().skip(1) {
+      if let Some(earlier) = self.shadowing(children, j) {
+        denied |= self.grammar.lint(Lint::UnreachableAlternative, self.grammar.rule_of_expr(this), vec![
+          (self.grammar[later].span(),
+            format!("This alternative will never succeed.")),
+          (self.grammar[earlier].span(),
+            format!("This alternative always succeeds on the input of the later one."))
+        ]);
+      }
     }
+    denied
   }
 
-  fn visit_character_class(&mut self, _this: usize, char_class: CharacterClassExpr) -> Occurence{
-    let mut res = vec![];
-    for intervals in char_class.intervals{
-      for i in intervals.lo as u32 .. intervals.hi as u32 +1 {
-        if let Some(c) = from_u32(i) {
-          res.push(vec![(Char(c),One)])
+  fn prefixes(&self, expr: usize, depth: usize) -> Vec<Prefix> {
+    use ast::Expression::*;
+    let prefixes = match self.grammar.expr_by_index(expr) {
+      StrLiteral(lit) => vec![Prefix::empty().concat(&Prefix {
+        chars: lit.chars().map(CharSet::one).collect(),
+        complete: true
+      })],
+      ByteLiteral(bytes) => vec![Prefix::empty().concat(&Prefix {
+        chars: bytes.into_iter().map(|b| CharSet::one(b as char)).collect(),
+        complete: true
+      })],
+      CharacterClass(class) => vec![Prefix::single(CharSet::class(&class))],
+      AnySingleChar => vec![Prefix::single(CharSet::any())],
+      Sequence(children) => {
+        let mut prefixes = vec![Prefix::empty()];
+        for child in children {
+          let child_prefixes = self.prefixes(child, depth);
+          prefixes = prefixes.into_iter()
+            .flat_map(|prefix| {
+              if prefix.complete {
+                child_prefixes.iter().map(|p| prefix.concat(p)).collect()
+              }
+              else {
+                vec![prefix]
+              }
+            })
+            .collect();
+          prefixes = UnreachableRule::bound(prefixes);
         }
+        prefixes
       }
-    }
-    Occurence{
-      choice: res
-    }
-  }
-
-  fn visit_non_terminal_symbol(&mut self, _this: usize, _rule: Ident) -> Occurence{
-    Occurence{
-      choice: vec![]
-    }
-  }
-
-  fn visit_zero_or_more(&mut self, _this: usize, _child: usize) -> Occurence{
-    Occurence{
-      choice: vec![]
-    }
+      Choice(children) => children.into_iter()
+        .flat_map(|child| self.prefixes(child, depth).into_iter())
+        .collect(),
+      ZeroOrMore(child) => {
+        let mut prefixes = vec![Prefix::empty()];
+        prefixes.extend(self.prefixes(child, depth).into_iter().map(Prefix::opened));
+        prefixes
+      }
+      OneOrMore(child) => self.prefixes(child, depth).into_iter().map(Prefix::opened).collect(),
+      ZeroOrOne(child) => {
+        let mut prefixes = self.prefixes(child, depth);
+        prefixes.push(Prefix::empty());
+        prefixes
+      }
+      NotPredicate(_)
+    | AndPredicate(_) => vec![Prefix::empty()],
+      NonTerminalSymbol(rule) => {
+        if depth < MAX_RULE_DEPTH {
+          self.prefixes(self.grammar.expr_index_of_rule(rule), depth + 1)
+        }
+        else {
+          vec![Prefix::open()]
+        }
+      }
+      SemanticAction(child, _)
+    | StructAction(child, _, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child)
+    | Binding(_, child)
+    | Capture(child)
+    | AstNode(child, _, _) => self.prefixes(child, depth),
+      CountedRepeat(_, _)
+    | TokenPattern(_)
+    | Precedence(_, _)
+    | Indentation(_) => vec![Prefix::open()]
+    };
+    UnreachableRule::bound(prefixes)
   }
 
-  fn visit_one_or_more(&mut self, _this: usize, _child: usize) -> Occurence{
-    Occurence{
-      choice: vec![]
-    }
+  fn bound(prefixes: Vec<Prefix>) -> Vec<Prefix> {
+    if prefixes.len() > MAX_PREFIXES { vec![Prefix::open()] }
+    else { prefixes }
   }
 
-  fn visit_optional(&mut self, _this: usize, _child: usize) -> Occurence{
-    Occurence{
-      choice: vec![]
+  /// Interprets `expr` at position `pos` on every input starting with `prefix`.
+  fn run(&self, expr: usize, pos: usize, prefix: &Prefix, depth: usize) -> Outcome {
+    use ast::Expression::*;
+    match self.grammar.expr_by_index(expr) {
+      StrLiteral(lit) => self.run_chars(pos, prefix, lit.chars().map(CharSet::one).collect()),
+      ByteLiteral(bytes) => self.run_chars(pos, prefix, bytes.into_iter().map(|b| CharSet::one(b as char)).collect()),
+      CharacterClass(class) => self.run_chars(pos, prefix, vec![CharSet::class(&class)]),
+      AnySingleChar => self.run_chars(pos, prefix, vec![CharSet::any()]),
+      Sequence(children) => {
+        let mut pos = pos;
+        let len = children.len();
+        for (i, child) in children.into_iter().enumerate() {
+          match self.run(child, pos, prefix, depth) {
+            Outcome::Success(next) => pos = next,
+            Outcome::SuccessAnywhere if i + 1 == len => return Outcome::SuccessAnywhere,
+            Outcome::Fail => return Outcome::Fail,
+            _ => return Outcome::Unknown
+          }
+        }
+        Outcome::Success(pos)
+      }
+      Choice(children) => {
+        for child in children {
+          match self.run(child, pos, prefix, depth) {
+            Outcome::Fail => (),
+            outcome => return outcome
+          }
+        }
+        Outcome::Fail
+      }
+      ZeroOrMore(child) => self.run_star(child, pos, prefix, depth),
+      OneOrMore(child) => {
+        match self.run(child, pos, prefix, depth) {
+          Outcome::Success(next) => self.run_star(child, next, prefix, depth),
+          outcome => outcome
+        }
+      }
+      ZeroOrOne(child) => {
+        match self.run(child, pos, prefix, depth) {
+          Outcome::Fail => Outcome::Success(pos),
+          Outcome::Unknown => Outcome::SuccessAnywhere,
+          outcome => outcome
+        }
+      }
+      AndPredicate(child) => {
+        match self.run(child, pos, prefix, depth) {
+          Outcome::Success(_) | Outcome::SuccessAnywhere => Outcome::Success(pos),
+          outcome => outcome
+        }
+      }
+      NotPredicate(child) => {
+        match self.run(child, pos, prefix, depth) {
+          Outcome::Success(_) | Outcome::SuccessAnywhere => Outcome::Fail,
+          Outcome::Fail => Outcome::Success(pos),
+          Outcome::Unknown => Outcome::Unknown
+        }
+      }
+      NonTerminalSymbol(rule) => {
+        if depth < MAX_RULE_DEPTH {
+          self.run(self.grammar.expr_index_of_rule(rule), pos, prefix, depth + 1)
+        }
+        else {
+          Outcome::Unknown
+        }
+      }
+      SemanticAction(child, _)
+    | StructAction(child, _, _)
+    | TypeAscription(child, _)
+    | SpannedExpr(child)
+    | Binding(_, child)
+    | Capture(child)
+    | AstNode(child, _, _) => self.run(child, pos, prefix, depth),
+      CountedRepeat(_, _)
+    | TokenPattern(_)
+    | Precedence(_, _)
+    | Indentation(_) => Outcome::Unknown
     }
   }
 
-  fn visit_not_predicate(&mut self, _this: usize, _child: usize) -> Occurence{
-    Occurence{
-      choice: vec![]
+  /// Matches the characters `chars` in sequence: it fails if one character is never accepted, and is unknown if one is read outside of the prefix or only partly accepted.
+  fn run_chars(&self, pos: usize, prefix: &Prefix, chars: Vec<CharSet>) -> Outcome {
+    let mut unknown = false;
+    for (i, expected) in chars.iter().enumerate() {
+      match prefix.chars.get(pos + i) {
+        Some(input) if input.is_subset(expected) => (),
+        Some(input) if input.is_disjoint(expected) => return Outcome::Fail,
+        _ => unknown = true
+      }
     }
+    if unknown { Outcome::Unknown }
+    else { Outcome::Success(pos + chars.len()) }
   }
 
-  fn visit_and_predicate(&mut self, _this: usize, _child: usize) -> Occurence{
-    Occurence{
-      choice: vec![]
+  fn run_star(&self, child: usize, pos: usize, prefix: &Prefix, depth: usize) -> Outcome {
+    let mut pos = pos;
+    loop {
+      match self.run(child, pos, prefix, depth) {
+        Outcome::Success(next) if next > pos => pos = next,
+        Outcome::Success(_)
+      | Outcome::Fail => return Outcome::Success(pos),
+        Outcome::SuccessAnywhere
+      | Outcome::Unknown => return Outcome::SuccessAnywhere
+      }
     }
   }
 }
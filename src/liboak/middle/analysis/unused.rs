@@ -0,0 +1,179 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reports the rules that cannot be reached from the entry points of the grammar and the Rust functions never used as semantic actions.
+//!
+//! The entry points are the rules annotated with `#[entry]` or, if there is none, the rules that are not called by another rule: a group of rules only calling each other, such as `a = "(" b` and `b = a ")"`, is then reported. The skip rule is always considered as reachable. A function is used if it is called in a semantic action or a precedence level of a reachable rule, or if its name appears in another Rust item of the grammar (e.g. a helper function called from an action).
+
+use middle::analysis::ast::*;
+use rust;
+use std::collections::HashSet;
+
+pub struct UnusedItems<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c AGrammar<'a, 'b>,
+  reachable_rules: HashSet<Ident>,
+  used_actions: HashSet<Ident>,
+  to_visit: Vec<Ident>
+}
+
+impl<'a, 'b, 'c> UnusedItems<'a, 'b, 'c>
+{
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if UnusedItems::check_unused(&grammar) {
+      Partial::Nothing
+    } else {
+      Partial::Value(grammar)
+    }
+  }
+
+  /// Returns `true` if a denied lint is raised.
+  fn check_unused(grammar: &'c AGrammar<'a, 'b>) -> bool {
+    let mut analyser = UnusedItems {
+      grammar: grammar,
+      reachable_rules: HashSet::new(),
+      used_actions: HashSet::new(),
+      to_visit: vec![]
+    };
+    // An undeclared skip rule is reported by `SkipRewriting`.
+    let skip = grammar.attributes.skip.iter()
+      .map(|skip| skip.node)
+      .filter(|skip| grammar.rules.iter().any(|rule| rule.ident() == *skip))
+      .collect();
+    analyser.visit_rules(skip);
+    analyser.visit_rules(analyser.entry_points());
+    let denied_rules = analyser.warn_unused_rules();
+    let denied_functions = analyser.warn_unused_functions();
+    denied_rules || denied_functions
+  }
+
+  fn entry_points(&self) -> Vec<Ident> {
+    let entries: Vec<_> = self.grammar.rules.iter()
+      .map(|rule| rule.ident())
+      .filter(|rule| self.grammar.attributes.is_entry(*rule))
+      .collect();
+    if entries.is_empty() {
+      let called = self.called_by_other_rules();
+      self.grammar.rules.iter()
+        .map(|rule| rule.ident())
+        .filter(|rule| !called.contains(rule))
+        .collect()
+    }
+    else {
+      entries
+    }
+  }
+
+  /// The rules called by a rule other than themselves.
+  fn called_by_other_rules(&self) -> HashSet<Ident> {
+    let mut called = HashSet::new();
+    for rule in &self.grammar.rules {
+      let mut to_visit = vec![rule.expr_idx];
+      while let Some(e) = to_visit.pop() {
+        match self.grammar.exprs[e] {
+          Expression::NonTerminalSymbol(callee) if callee != rule.ident() => { called.insert(callee); }
+          _ => ()
+        }
+        to_visit.extend(self.grammar.exprs[e].children());
+      }
+    }
+    called
+  }
+
+  fn visit_rules(&mut self, rules: Vec<Ident>) {
+    self.to_visit.extend(rules);
+    while let Some(rule) = self.to_visit.pop() {
+      if self.reachable_rules.insert(rule) {
+        let expr_idx = self.grammar.expr_index_of_rule(rule);
+        self.visit_expr(expr_idx);
+      }
+    }
+  }
+
+  fn warn_unused_rules(&self) -> bool {
+    let mut denied = false;
+    for rule in &self.grammar.rules {
+      if !self.reachable_rules.contains(&rule.ident()) {
+        denied |= self.grammar.lint(Lint::UnusedRule, Some(rule.ident()), vec![(rule.span(), format!(
+          "In rule {}: this rule is never used from the entry points of the grammar. \
+           Annotate it with `#[entry]` if it is called from outside the grammar.",
+          rule.ident()))]);
+      }
+    }
+    denied
+  }
+
+  fn warn_unused_functions(&self) -> bool {
+    let mut denied = false;
+    let mut functions: Vec<_> = self.grammar.rust_functions.iter()
+      .filter(|&(name, _)| !self.used_actions.contains(name))
+      .collect();
+    functions.sort_by_key(|&(name, _)| name.name.as_str().to_string());
+    for (name, item) in functions {
+      if !self.used_in_rust_items(*name) {
+        denied |= self.grammar.lint(Lint::UnusedAction, None, vec![(item.span, format!(
+          "The function `{}` is never used as a semantic action.", name))]);
+      }
+    }
+    denied
+  }
+
+  /// `true` if `name` occurs in the source of another function or item of the grammar.
+  fn used_in_rust_items(&self, name: Ident) -> bool {
+    let name = name.name.as_str();
+    let name: &str = &*name;
+    self.grammar.rust_functions.values()
+      .filter(|item| item.ident.name.as_str() != name)
+      .chain(self.grammar.rust_items.iter())
+      .any(|item| {
+        rust::item_to_string(item)
+          .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+          .any(|word| word == name)
+      })
+  }
+}
+
+impl<'a, 'b, 'c> ExprByIndex for UnusedItems<'a, 'b, 'c>
+{
+  fn expr_by_index(&self, index: usize) -> Expression {
+    self.grammar.expr_by_index(index)
+  }
+}
+
+impl<'a, 'b, 'c> Visitor<()> for UnusedItems<'a, 'b, 'c>
+{
+  unit_visitor_impl!(str_literal);
+  unit_visitor_impl!(atom);
+  unit_visitor_impl!(sequence);
+  unit_visitor_impl!(choice);
+
+  fn visit_non_terminal_symbol(&mut self, _this: usize, rule: Ident) {
+    if !self.reachable_rules.contains(&rule) {
+      self.to_visit.push(rule);
+    }
+  }
+
+  fn visit_semantic_action(&mut self, _this: usize, child: usize, action: Ident) {
+    self.used_actions.insert(action);
+    self.visit_expr(child);
+  }
+
+  fn visit_precedence(&mut self, _this: usize, atom: usize, levels: Vec<PrecedenceLevel>) {
+    for level in levels {
+      self.used_actions.insert(level.action);
+      self.visit_expr(level.op);
+    }
+    self.visit_expr(atom);
+  }
+}
@@ -16,6 +16,7 @@
 use middle::analysis::ast::*;
 use ast::Expression::*;
 use std::cmp;
+use std::cell::Cell;
 use syntax::codemap::BytePos;
 pub use rust::NO_EXPANSION;
 
@@ -36,6 +37,8 @@ enum VecType {
 pub struct UselessChaining<'a: 'c, 'b: 'a, 'c>
 {
   grammar: &'c AGrammar<'a, 'b>,
+  rule: Option<Ident>,
+  has_denied: Cell<bool>,
   pred: Predicate,
   vec_pred : Vec<Predicate>,
   vec_type : VecType
@@ -44,20 +47,28 @@ pub struct UselessChaining<'a: 'c, 'b: 'a, 'c>
 impl <'a, 'b, 'c> UselessChaining<'a, 'b, 'c>
 {
   pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
-    UselessChaining::check_chaining(&grammar);
-    Partial::Value(grammar)
+    if UselessChaining::check_chaining(&grammar) {
+      Partial::Nothing
+    } else {
+      Partial::Value(grammar)
+    }
   }
 
-  fn check_chaining(grammar: &'c AGrammar<'a, 'b>){
+  /// Returns `true` if a denied lint is raised.
+  fn check_chaining(grammar: &'c AGrammar<'a, 'b>) -> bool {
     let mut analyser = UselessChaining{
       grammar: grammar,
+      rule: None,
+      has_denied: Cell::new(false),
       pred: Predicate::Nothing,
       vec_pred: vec![],
       vec_type: VecType::Nothing
     };
     for rule in &grammar.rules {
+      analyser.rule = Some(rule.ident());
       analyser.visit_expr(rule.expr_idx)
     }
+    analyser.has_denied.get()
   }
 
   fn span_end_point(&self, sp: Span) -> Span{
@@ -76,57 +87,52 @@ impl <'a, 'b, 'c> UselessChaining<'a, 'b, 'c>
 
   fn warn_useless_chaining(&self, pred: (&'static str, &'static str, &'static str, &'static str), span2: Span, span1: Span){
       let (detected,help,first,second) = pred;
-      self.grammar.span_warn(
-          span1,
+      let denied = self.grammar.lint(Lint::UselessChaining, self.rule, vec![
+          (span1,
           format!(
               "Detected useless chaining: {}
               \nHelp: {}
               \n1{} predicate {}"
-          ,detected,help,self.get_th(1),first)
-      );
-      self.grammar.span_note(
-          span2,
-          format!("2{} predicate {}",self.get_th(2),second)
-      )
+          ,detected,help,self.get_th(1),first)),
+          (span2,
+          format!("2{} predicate {}",self.get_th(2),second))
+      ]);
+      self.has_denied.set(self.has_denied.get() || denied);
   }
 
-  fn warn_vec_useless_chaining(&self, pred: (&'static str, &'static str, &'static str), i: usize, span: Span){
+  fn vec_useless_chaining_message(&self, pred: (&'static str, &'static str, &'static str), i: usize) -> String {
       let (warn,note,span_note) = pred;
       if i==0 {
-          self.grammar.span_warn(
-              span,
-              format!(
-                  "Detected useless chaining: multiple {}
-                  \nHelp: {}
-                  \n1{} occurence of {}"
-              ,warn,note,self.get_th(1),warn)
-          )
+          format!(
+              "Detected useless chaining: multiple {}
+              \nHelp: {}
+              \n1{} occurence of {}"
+          ,warn,note,self.get_th(1),warn)
       }
       else{
-          self.grammar.span_note(
-              span,
-              format!("{}{} occurence of {}",i+1,self.get_th(i+1),span_note)
-          )
+          format!("{}{} occurence of {}",i+1,self.get_th(i+1),span_note)
       }
-
   }
 
   fn warn_verify_multiple(&self) {
+      let mut messages = vec![];
       for (i, x) in self.vec_pred.iter().enumerate() {
           match x {
               &Predicate::And(this) => {
                   let lo = self.grammar[this].span().lo();
                   let span = Span::new(lo,lo,NO_EXPANSION);
-                  self.warn_vec_useless_chaining(("&","&(&e) -> &e","and"),i,span);
+                  messages.push((span, self.vec_useless_chaining_message(("&","&(&e) -> &e","and"),i)));
               }
               &Predicate::Oom(this) => {
                   let sp = self.grammar[this].span();
                   let span = self.span_end_point(sp);
-                  self.warn_vec_useless_chaining(("+","(e+)+ -> e+","one or more"),i,span);
+                  messages.push((span, self.vec_useless_chaining_message(("+","(e+)+ -> e+","one or more"),i)));
               }
               _ => unreachable!()
           }
       }
+      let denied = self.grammar.lint(Lint::UselessChaining, self.rule, messages);
+      self.has_denied.set(self.has_denied.get() || denied);
   }
 
   fn verify_multiple(&mut self){
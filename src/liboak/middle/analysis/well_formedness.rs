@@ -13,16 +13,16 @@
 // limitations under the License.
 
 use middle::analysis::ast::*;
-use std::mem::swap;
+use std::mem::{swap, replace};
 use std::collections::{HashMap, HashSet};
 
 /// Well-formedness attributes, it represents the possible behavior of an expression.
 #[derive(Clone, Copy, PartialEq, Eq)]
-struct WFA
+pub struct WFA
 {
-  can_fail: bool,
-  can_succeed: bool,
-  always_consume: bool,
+  pub can_fail: bool,
+  pub can_succeed: bool,
+  pub always_consume: bool,
 }
 
 impl WFA
@@ -52,25 +52,29 @@ pub struct WellFormedness<'a: 'c, 'b: 'a, 'c>
   rules_wfa: HashMap<Ident, WFA>,
   reached_fixpoint: bool,
   well_formed: bool,
-  errors: HashSet<usize> // Whether we already spot an error on this rule (to avoid multi-reporting).
+  errors: HashSet<usize>, // Whether we already spot an error on this rule (to avoid multi-reporting).
+  labels: Vec<Ident>, // Labels bound by the previous elements of the enclosing sequences.
+  exprs_wfa: HashMap<usize, WFA>
 }
 
 // Start with an empty set of the expression attributes.
 
 impl<'a, 'b, 'c> WellFormedness<'a, 'b, 'c>
 {
-  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
-    if WellFormedness::is_well_formed(&grammar) {
-      Partial::Value(grammar)
-    } else {
-      Partial::Nothing
+  /// Returns the grammar with the attributes of its expressions, they are used by the analyses following this one.
+  pub fn analyse(grammar: AGrammar<'a, 'b>) -> Partial<(AGrammar<'a, 'b>, HashMap<usize, WFA>)> {
+    match WellFormedness::expressions_attributes(&grammar) {
+      Some(exprs_wfa) => Partial::Value((grammar, exprs_wfa)),
+      None => Partial::Nothing
     }
   }
 
-  fn is_well_formed(grammar: &'c AGrammar<'a, 'b>) -> bool {
+  /// The attributes of every expression, `None` if the grammar is not well-formed.
+  fn expressions_attributes(grammar: &'c AGrammar<'a, 'b>) -> Option<HashMap<usize, WFA>> {
     let mut analyser = WellFormedness::new(grammar);
     analyser.visit_rules();
-    analyser.well_formed
+    if analyser.well_formed { Some(analyser.exprs_wfa) }
+    else { None }
   }
 
   fn new(grammar: &'c AGrammar<'a, 'b>) -> Self {
@@ -83,7 +87,9 @@ impl<'a, 'b, 'c> WellFormedness<'a, 'b, 'c>
         .collect(),
       reached_fixpoint: false,
       well_formed: true,
-      errors: HashSet::new()
+      errors: HashSet::new(),
+      labels: vec![],
+      exprs_wfa: HashMap::new()
     }
   }
 
@@ -105,7 +111,9 @@ impl<'a, 'b, 'c> WellFormedness<'a, 'b, 'c>
     }
     else {
       self.push_rule_in_path(rule);
+      let labels = replace(&mut self.labels, vec![]);
       let wfa = self.visit_rule_expr(rule);
+      self.labels = labels;
       self.pop_rule_in_path();
       self.fixpoint_update(wfa, rule);
     }
@@ -201,6 +209,27 @@ impl<'a, 'b, 'c> WellFormedness<'a, 'b, 'c>
     }
   }
 
+  fn error_loop_operator(&mut self, op_idx: usize) {
+    if self.register_error(op_idx) {
+      self.well_formed = false;
+      self.grammar.span_err(self.grammar[op_idx].span(), format!(
+        "Infinite loop detected. An operator of a precedence expression \
+        is repeated while it succeeds but it does not consume input.\n\
+        Solution: Rewrite the operator such that it consumes at least \
+        one atom in the input."));
+    }
+  }
+
+  fn error_unbound_count(&mut self, expr_idx: usize, count: Ident) {
+    if self.register_error(expr_idx) {
+      self.well_formed = false;
+      self.grammar.span_err(self.grammar[expr_idx].span(), format!(
+        "The count `{}` of this repetition is not bound earlier in the sequence.\n\
+        Solution: Label the expression giving the count before the repetition \
+        such as in `{}:number \":\" byte{{{}}}`.", count, count, count));
+    }
+  }
+
   fn error_unreachable_branches(&mut self, choice: usize, always_succeed_branch: usize)
   {
     if self.register_error(always_succeed_branch) {
@@ -216,6 +245,13 @@ impl<'a, 'b, 'c> WellFormedness<'a, 'b, 'c>
     }
   }
 
+  fn visit_operator(&mut self, op_idx: usize) {
+    let op_wfa = self.visit_expr(op_idx);
+    if op_wfa.can_succeed && !op_wfa.always_consume {
+      self.error_loop_operator(op_idx);
+    }
+  }
+
   fn register_error(&mut self, expr_idx: usize) -> bool {
     self.errors.insert(expr_idx)
   }
@@ -238,6 +274,7 @@ impl<'a, 'b, 'c> Visitor<WFA> for WellFormedness<'a, 'b, 'c>
       self.error_never_succeed(this);
       wfa.can_succeed = true; // Error-recovery.
     }
+    self.exprs_wfa.insert(this, wfa);
     wfa
   }
 
@@ -250,6 +287,15 @@ impl<'a, 'b, 'c> Visitor<WFA> for WellFormedness<'a, 'b, 'c>
     wfa
   }
 
+  fn visit_byte_literal(&mut self, _this: usize, bytes: Vec<u8>) -> WFA {
+    let mut wfa = WFA::all_true();
+    if bytes.is_empty() {
+      wfa.can_fail = false;
+      wfa.always_consume = false;
+    }
+    wfa
+  }
+
   fn visit_non_terminal_symbol(&mut self, _this: usize, rule: Ident) -> WFA {
     self.visit_rule(rule)
   }
@@ -258,6 +304,13 @@ impl<'a, 'b, 'c> Visitor<WFA> for WellFormedness<'a, 'b, 'c>
     WFA::all_true()
   }
 
+  // Only `indent` is guaranteed to consume: `samedent` does not consume at the top-level block and `dedent` never consumes.
+  fn visit_indentation(&mut self, _this: usize, kind: IndentKind) -> WFA {
+    let mut wfa = WFA::all_true();
+    wfa.always_consume = kind == IndentKind::Indent;
+    wfa
+  }
+
   fn visit_repeat(&mut self, this: usize, child: usize) -> WFA {
     let child_wfa = self.visit_expr(child);
     if child_wfa.can_succeed && !child_wfa.always_consume {
@@ -269,6 +322,17 @@ impl<'a, 'b, 'c> Visitor<WFA> for WellFormedness<'a, 'b, 'c>
     }
   }
 
+  // The count can be zero so the repetition might not consume input, but it does not loop forever.
+  fn visit_counted_repeat(&mut self, this: usize, child: usize, count: Ident) -> WFA {
+    if !self.labels.contains(&count) {
+      self.error_unbound_count(this, count);
+    }
+    let mut wfa = self.visit_expr(child);
+    wfa.can_succeed = true;
+    wfa.always_consume = false;
+    wfa
+  }
+
   fn visit_zero_or_more(&mut self, this: usize, child: usize) -> WFA {
     self.visit_repeat(this, child);
     WFA::always_succeed()
@@ -314,6 +378,24 @@ impl<'a, 'b, 'c> Visitor<WFA> for WellFormedness<'a, 'b, 'c>
     wfa
   }
 
+  fn visit_precedence(&mut self, _this: usize, atom: usize, levels: Vec<PrecedenceLevel>) -> WFA {
+    let savepoint = self.save();
+    let (prefixes, operators): (Vec<_>, Vec<_>) = levels.into_iter()
+      .partition(|level| level.assoc == Associativity::Prefix);
+    for level in prefixes {
+      self.visit_operator(level.op);
+    }
+    let atom_wfa = self.visit_expr(atom);
+    if atom_wfa.always_consume {
+      self.consumed_input = true;
+    }
+    for level in operators {
+      self.visit_operator(level.op);
+    }
+    self.restore(savepoint);
+    atom_wfa
+  }
+
   fn visit_sequence(&mut self, _this: usize, children: Vec<usize>) -> WFA {
     let savepoint = self.save();
     let mut wfa = WFA {
@@ -321,6 +403,7 @@ impl<'a, 'b, 'c> Visitor<WFA> for WellFormedness<'a, 'b, 'c>
       can_succeed: true,
       always_consume: false
     };
+    let labels_len = self.labels.len();
     for child in children {
       let child_wfa = self.visit_expr(child);
       wfa.can_fail |= child_wfa.can_fail;
@@ -329,7 +412,11 @@ impl<'a, 'b, 'c> Visitor<WFA> for WellFormedness<'a, 'b, 'c>
       if child_wfa.always_consume {
         self.consumed_input = true;
       }
+      if let Expression::Binding(label, _) = self.expr_by_index(child) {
+        self.labels.push(label);
+      }
     }
+    self.labels.truncate(labels_len);
     self.restore(savepoint);
     wfa
   }
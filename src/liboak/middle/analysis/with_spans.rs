@@ -0,0 +1,67 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implements `#![with_spans]`: every semantic action `e > f` is rewritten into `e >@ f`, that is `..e > f`, so the span of `e` is passed as the first argument of `f`.
+//!
+//! The actions already receiving a span (`..e > f`) are not rewritten. The actions called with labels, the structure actions `e > S {..}` and the actions of the precedence levels do not receive the span: a warning is reported so the span is not silently missing.
+
+use middle::analysis::ast::*;
+use ast::Expression::*;
+
+pub struct WithSpans;
+
+impl WithSpans
+{
+  pub fn rewrite<'a, 'b>(mut grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+    if grammar.attributes.with_spans {
+      for this in 0..grammar.exprs.len() {
+        match grammar.exprs[this].clone() {
+          SemanticAction(child, action) => {
+            let spanned = match grammar.exprs[child] {
+              SpannedExpr(_) => true,
+              _ => false
+            };
+            if !spanned && grammar.bound_labels(child).is_empty() {
+              let span = grammar[child].span();
+              let child = grammar.alloc_expr(span, SpannedExpr(child));
+              grammar.exprs[this] = SemanticAction(child, action);
+            }
+            else if !spanned {
+              WithSpans::warn_no_span(&grammar, this, format!("the action `{}` called with labels", action),
+                format!("add a label to a spanned expression, such as `sp:(..e)`"));
+            }
+          }
+          StructAction(_, name, _) => {
+            WithSpans::warn_no_span(&grammar, this, format!("the structure `{}`", name),
+              format!("add a field receiving a spanned expression, such as `sp:(..e)`"));
+          }
+          Precedence(_, levels) => {
+            for level in levels {
+              WithSpans::warn_no_span(&grammar, level.op, format!("the action `{}` of this precedence level", level.action),
+                format!("build the span from the spans of the operands"));
+            }
+          }
+          _ => ()
+        }
+      }
+    }
+    Partial::Value(grammar)
+  }
+
+  fn warn_no_span(grammar: &AGrammar, this: usize, target: String, help: String) {
+    grammar.span_warn(grammar[this].span(), format!(
+      "`#![with_spans]` does not pass the span to {}.\n\
+       Help: {}.", target, help));
+  }
+}
@@ -24,13 +24,16 @@ pub use front::ast::FGrammar;
 
 pub mod analysis;
 pub mod typing;
+pub mod optimizer;
 
 pub fn typecheck<'a, 'b>(cx: &'a ExtCtxt<'b>, fgrammar: FGrammar) -> Partial<TGrammar<'a, 'b>> {
   Partial::Value(fgrammar)
     .and_then(|grammar| at_least_one_rule_declared(cx, grammar))
     .and_then(|grammar| analysis::analyse(cx, grammar))
     .and_then(|grammar| extract_stream_type(grammar))
+    .and_then(|grammar| check_atom_kinds(grammar))
     .and_then(|grammar| typing::type_inference(grammar))
+    .and_then(|grammar| optimizer::Optimizer::optimize(grammar))
 }
 
 fn at_least_one_rule_declared(cx: &ExtCtxt, fgrammar: FGrammar) -> Partial<FGrammar> {
@@ -69,3 +72,34 @@ fn extract_stream_type<'a, 'b>(mut grammar: AGrammar<'a, 'b>)
   }
   Partial::Value(grammar)
 }
+
+/// The string literals and character classes are matched against characters and their byte versions against bytes: they are rejected when the type of the items of the stream is known and does not match.
+fn check_atom_kinds<'a, 'b>(grammar: AGrammar<'a, 'b>) -> Partial<AGrammar<'a, 'b>> {
+  use middle::analysis::ast::*;
+  let item_ty = match grammar.stream_item_type() {
+    Some(ty) => rust::ty_to_string(&*ty),
+    None => return Partial::Value(grammar)
+  };
+  let mut mismatch = false;
+  for (expr_idx, expr) in grammar.exprs.iter().enumerate() {
+    let kind = match expr {
+      &Expression::StrLiteral(_) => AtomKind::Char,
+      &Expression::ByteLiteral(_) => AtomKind::Byte,
+      &Expression::CharacterClass(ref class) => class.kind,
+      _ => continue
+    };
+    let (expected, help) = match kind {
+      AtomKind::Char => ("char", "use a byte literal such as `b\"a\"` or a byte class such as `[b\"a-z\"]`"),
+      AtomKind::Byte => ("u8", "use a string literal such as `\"a\"` or a character class such as `[\"a-z\"]`")
+    };
+    if item_ty != expected {
+      mismatch = true;
+      grammar.expr_err(expr_idx, format!(
+        "This expression matches items of type `{}` but the stream produces items of type `{}`.\n\
+         Help: {}, or change the type `Stream` declared in the grammar.",
+        expected, item_ty, if item_ty == "char" || item_ty == "u8" { help } else { "match the tokens with patterns" }));
+    }
+  }
+  if mismatch { Partial::Nothing }
+  else { Partial::Value(grammar) }
+}
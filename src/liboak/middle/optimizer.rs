@@ -0,0 +1,218 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Normalizes the typed grammar before the code generation:
+//!
+//! * nested sequences and choices are flattened, such as `a (b c)` into `a b c`,
+//! * adjacent character classes in a choice are merged, such as `["a"] / ["b"]` into `["ab"]`,
+//! * consecutive literals in a sequence are folded, such as `"a" "b"` into `"ab"`,
+//! * redundant predicates are removed, such as `!!e` into `&e` or `&!e` into `!e`,
+//! * a forwarding rule `a = b` is replaced by `b` where `a` is called.
+//!
+//! The folding of literals is visible at runtime: when `"a" "b"` fails after `a`, the error is reported at the start of the folded literal `"ab"`, which is given as the expected item, instead of after `a` with `"b"` expected.
+//!
+//! Expressions are rewritten in place, so their spans and types, which refer to the indexes of the sub-expressions, are preserved. A rewriting is only done if it does not change the type of the expression. The rewritings are printed with `#![debug_typing]`.
+
+use middle::typing::ast::*;
+use middle::typing::ast::Type::*;
+
+pub struct Optimizer<'a: 'c, 'b: 'a, 'c>
+{
+  grammar: &'c mut TGrammar<'a, 'b>,
+  debug: bool
+}
+
+impl<'a, 'b, 'c> Optimizer<'a, 'b, 'c>
+{
+  pub fn optimize(mut grammar: TGrammar<'a, 'b>) -> Partial<TGrammar<'a, 'b>> {
+    let debug = grammar.attributes.print_typing.debug();
+    {
+      let mut optimizer = Optimizer {
+        grammar: &mut grammar,
+        debug: debug
+      };
+      while optimizer.rewrite_all() {}
+    }
+    Partial::Value(grammar)
+  }
+
+  fn rewrite_all(&mut self) -> bool {
+    let mut changed = false;
+    for this in 0..self.grammar.exprs.len() {
+      changed |= match self.grammar.exprs[this].clone() {
+        Sequence(children) => self.rewrite_sequence(this, children),
+        Choice(children) => self.rewrite_choice(this, children),
+        NotPredicate(child) => self.rewrite_predicate(this, child, false),
+        AndPredicate(child) => self.rewrite_predicate(this, child, true),
+        NonTerminalSymbol(rule) => self.rewrite_forwarding(this, rule),
+        _ => false
+      };
+    }
+    changed
+  }
+
+  fn report(&self, this: usize, rewriting: &str) {
+    if self.debug {
+      println!("Optimizer: {} in expression {}: {:?}", rewriting, this, self.grammar.exprs[this]);
+    }
+  }
+
+  fn rewrite_sequence(&mut self, this: usize, children: Vec<usize>) -> bool {
+    let mut seq = vec![];
+    let mut flattened = false;
+    let mut folded = false;
+    for child in children.clone() {
+      let child = match self.grammar.exprs[child].clone() {
+        // The labels of a nested sequence are not bound in the enclosing one.
+        Sequence(ref inner) if !self.has_binding(inner) => {
+          flattened = true;
+          seq.extend(inner.iter().cloned());
+          continue;
+        }
+        _ => child
+      };
+      let fold = match seq.last() {
+        Some(&previous) => self.fold_literals(previous, child),
+        None => false
+      };
+      if fold { folded = true; } else { seq.push(child); }
+    }
+    if flattened { self.report(this, "flattened a nested sequence"); }
+    if folded { self.report(this, "folded consecutive literals"); }
+    self.replace_children(this, children, seq, Sequence)
+  }
+
+  fn rewrite_choice(&mut self, this: usize, children: Vec<usize>) -> bool {
+    let mut choices = vec![];
+    let mut flattened = false;
+    let mut merged = false;
+    for child in children.clone() {
+      let child = match self.grammar.exprs[child].clone() {
+        Choice(ref inner) if self.type_eq(this, child) => {
+          flattened = true;
+          choices.extend(inner.iter().cloned());
+          continue;
+        }
+        _ => child
+      };
+      let merge = match choices.last() {
+        Some(&previous) => self.merge_classes(previous, child),
+        None => false
+      };
+      if merge { merged = true; } else { choices.push(child); }
+    }
+    if flattened { self.report(this, "flattened a nested choice"); }
+    if merged { self.report(this, "merged adjacent character classes"); }
+    self.replace_children(this, children, choices, Choice)
+  }
+
+  /// A sequence or a choice with a single element is replaced by this element, unless it binds a label.
+  fn replace_children<F>(&mut self, this: usize, old: Vec<usize>, new: Vec<usize>, make: F) -> bool where
+   F: Fn(Vec<usize>) -> Expression
+  {
+    if new.len() == 1 && !self.has_binding(&new) && self.type_eq(this, new[0]) {
+      self.grammar.exprs[this] = self.grammar.exprs[new[0]].clone();
+      self.report(this, "removed a sequence or choice with a single element");
+      true
+    }
+    else if new != old {
+      self.grammar.exprs[this] = make(new);
+      true
+    }
+    else {
+      false
+    }
+  }
+
+  /// Folds the literal `next` into the literal `previous`.
+  fn fold_literals(&mut self, previous: usize, next: usize) -> bool {
+    let folded = match (self.grammar.exprs[previous].clone(), self.grammar.exprs[next].clone()) {
+      (StrLiteral(mut lit1), StrLiteral(lit2)) => {
+        lit1.push_str(&lit2);
+        StrLiteral(lit1)
+      }
+      (ByteLiteral(mut bytes1), ByteLiteral(bytes2)) => {
+        bytes1.extend(bytes2);
+        ByteLiteral(bytes1)
+      }
+      _ => return false
+    };
+    if !self.type_eq(previous, next) {
+      return false;
+    }
+    self.grammar.exprs[previous] = folded;
+    self.extend_span(previous, next);
+    true
+  }
+
+  /// Merges the character class `next` into the character class `previous`, they both match a single character so the order of the alternatives does not matter.
+  fn merge_classes(&mut self, previous: usize, next: usize) -> bool {
+    match (self.grammar.exprs[previous].clone(), self.grammar.exprs[next].clone()) {
+      (CharacterClass(mut class1), CharacterClass(class2))
+        if class1.kind == class2.kind && self.type_eq(previous, next) =>
+      {
+        class1.intervals.extend(class2.intervals);
+        self.grammar.exprs[previous] = CharacterClass(class1);
+        self.extend_span(previous, next);
+        true
+      }
+      _ => false
+    }
+  }
+
+  fn extend_span(&mut self, this: usize, next: usize) {
+    let span = self.grammar[this].span.to(self.grammar[next].span);
+    self.grammar[this].span = span;
+  }
+
+  /// `!!e` and `&&e` are rewritten into `&e`, `&!e` and `!&e` into `!e`.
+  fn rewrite_predicate(&mut self, this: usize, child: usize, and: bool) -> bool {
+    let (child_and, grandchild) = match self.grammar.exprs[child] {
+      AndPredicate(grandchild) => (true, grandchild),
+      NotPredicate(grandchild) => (false, grandchild),
+      _ => return false
+    };
+    self.grammar.exprs[this] =
+      if and == child_and { AndPredicate(grandchild) }
+      else { NotPredicate(grandchild) };
+    self.report(this, "removed a redundant predicate");
+    true
+  }
+
+  /// The call of a rule `rule = other` is replaced by a call of `other`.
+  fn rewrite_forwarding(&mut self, this: usize, rule: Ident) -> bool {
+    let rule_expr = self.grammar.expr_index_of_rule(rule);
+    match self.grammar.exprs[rule_expr] {
+      NonTerminalSymbol(other) if other != rule
+        && self.type_eq(rule_expr, self.grammar.expr_index_of_rule(other)) =>
+      {
+        self.grammar.exprs[this] = NonTerminalSymbol(other);
+        self.report(this, "inlined a forwarding rule");
+        true
+      }
+      _ => false
+    }
+  }
+
+  fn has_binding(&self, exprs: &Vec<usize>) -> bool {
+    exprs.iter().any(|&idx| match self.grammar.exprs[idx] {
+      Binding(..) => true,
+      _ => false
+    })
+  }
+
+  fn type_eq(&self, expr1: usize, expr2: usize) -> bool {
+    self.grammar[expr1].ty.eq_by(&self.grammar[expr2].ty, |e1, e2| self.type_eq(e1, e2))
+  }
+}
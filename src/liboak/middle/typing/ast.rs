@@ -20,6 +20,8 @@ pub use visitor::*;
 pub use ast::Expression::*;
 
 use rust;
+use rust::AstBuilder;
+use middle::typing::instantiation::Instantiation;
 use middle::typing::ast::Type::*;
 use middle::typing::ast::IType::*;
 use middle::analysis::ast::AGrammar;
@@ -38,6 +40,7 @@ impl<'a, 'b> IGrammar<'a, 'b>
       exprs: agrammar.exprs,
       exprs_info: vec![],
       stream_alias: agrammar.stream_alias,
+      generics: agrammar.generics,
       rust_functions: agrammar.rust_functions,
       rust_items: agrammar.rust_items,
       attributes: agrammar.attributes
@@ -45,7 +48,6 @@ impl<'a, 'b> IGrammar<'a, 'b>
     grammar.exprs_info = exprs_info.into_iter()
       .map(|e| ExprIType::infer(e.span))
       .collect();
-    grammar.alloc_span_ty_expr();
     grammar
   }
 
@@ -63,10 +65,133 @@ impl<'a, 'b> IGrammar<'a, 'b>
     }
   }
 
+  /// The generics of the function `action`, `None` if it has no type parameters.
+  pub fn action_generics(&self, action: Ident) -> Option<rust::Generics> {
+    match self.rust_functions[&action].node {
+      rust::ItemKind::Fn(_,_,_,_, ref generics, _)
+        if Instantiation::new(generics).is_generic() => Some(generics.clone()),
+      _ => None
+    }
+  }
+
+  /// The type of `action` where its type parameters are instantiated with the types of the arguments produced by `child`. We fall back to `action_type` if the types of the arguments are not yet known (e.g. in a recursive rule) or if some parameters can not be instantiated, the latter is reported by `Depth` unless these parameters are the ones of the grammar.
+  pub fn instantiated_action_type(&self, this: usize, child: usize, action: Ident) -> IType
+  {
+    match self.action_instantiation(child, action) {
+      Some((ref instantiation, ref output)) if instantiation.is_instantiated(output) =>
+        IType::rust_type(instantiation.substitute(output.clone())),
+      _ => self.action_type(this, action)
+    }
+  }
+
+  /// Unifies the parameters of the generic function `action` with the types of the arguments produced by `child`, and returns the instantiation with the output type of `action`. `None` if `action` is not generic, has no output type or if the types of the arguments are not yet known.
+  pub fn action_instantiation(&self, child: usize, action: Ident) -> Option<(Instantiation, RTy)> {
+    let generics = self.action_generics(action)?;
+    let (inputs, output) = match self.rust_functions[&action].node {
+      rust::ItemKind::Fn(ref decl,..) => (decl.inputs.clone(), decl.output.clone()),
+      _ => return None
+    };
+    let output = match output {
+      rust::FunctionRetTy::Ty(output) => output,
+      _ => return None
+    };
+    let arg_types = self.action_arg_types(child, action)?;
+    let mut instantiation = Instantiation::new(&generics);
+    for (param, arg) in inputs.iter().zip(arg_types.iter()) {
+      instantiation.unify(&param.ty, arg);
+    }
+    Some((instantiation, output))
+  }
+
+  /// The types of the arguments passed to `action` by `expr_idx`, in the order of its parameters.
+  pub fn action_arg_types(&self, expr_idx: usize, action: Ident) -> Option<Vec<RTy>> {
+    if let Some(labels) = self.named_action_args(expr_idx, action) {
+      return labels.into_iter()
+        .map(|label| self.labelled_expr(expr_idx, label).and_then(|e| self.rust_type_of(e)))
+        .collect();
+    }
+    self.rust_types_of(expr_idx)
+  }
+
+  /// The expression labelled with `label` in `expr_idx` (see `bound_labels`).
+  pub fn labelled_expr(&self, expr_idx: usize, label: Ident) -> Option<usize> {
+    let labelled = |idx: usize| match self.exprs[idx] {
+      Expression::Binding(l, child) if l == label => Some(child),
+      _ => None
+    };
+    match self.exprs[expr_idx] {
+      Expression::Sequence(ref seq) => seq.iter().filter_map(|&idx| labelled(idx)).next(),
+      _ => labelled(expr_idx)
+    }
+  }
+
+  /// The Rust type of the value produced by `expr_idx`, `None` if it is not yet inferred.
+  pub fn rust_type_of(&self, expr_idx: usize) -> Option<RTy> {
+    let cx = self.cx;
+    self.rust_types_of(expr_idx).map(|mut tys| {
+      if tys.len() == 1 { tys.pop().unwrap() }
+      else { cx.ty(cx.call_site(), rust::TyKind::Tup(tys)) }
+    })
+  }
+
+  /// The Rust types of the values produced by `expr_idx`, the tuples and spanned expressions being flattened. `None` if they are not yet inferred.
+  pub fn rust_types_of(&self, expr_idx: usize) -> Option<Vec<RTy>> {
+    let cx = self.cx;
+    match self.type_of(expr_idx) {
+      Invisible
+    | Regular(Unit) => Some(vec![]),
+      Regular(Atom) => Some(vec![self.atom_type()]),
+      Regular(Optional(child)) => self.rust_type_of(child).map(|ty| vec![quote_ty!(cx, Option<$ty>)]),
+      Regular(List(child)) => self.rust_type_of(child).map(|ty| vec![quote_ty!(cx, Vec<$ty>)]),
+      Regular(Collection(_, ty)) => Some(vec![ty]),
+      Regular(Spanned(child)) => self.rust_types_of(child).map(|tys| {
+        let mut spanned = vec![self.span_type()];
+        spanned.extend(tys);
+        spanned
+      }),
+      Regular(Tuple(children)) => children.into_iter()
+        .map(|child| self.rust_types_of(child))
+        .collect::<Option<Vec<_>>>()
+        .map(|tys| tys.into_iter().flat_map(|tys| tys.into_iter()).collect()),
+      Regular(Action(rust::FunctionRetTy::Ty(ty))) => Some(vec![ty]),
+      Regular(Action(rust::FunctionRetTy::Default(_))) => Some(vec![quote_ty!(cx, ())]),
+      Infer | Rec(_) => None
+    }
+  }
+
   pub fn type_of(&self, expr_idx: usize) -> IType {
     self[expr_idx].ty()
   }
 
+  /// Replaces the expression of `rule` by `AstNode(e, Type, None)` where `Type` is the name of the rule in camel case. The expression is moved to a new index so the parent expressions do not need to be updated. Returns `false` and reports an error if `Type` clashes with a name of the grammar or with another generated type.
+  pub fn wrap_rule_in_node(&mut self, rule: Ident) -> bool {
+    let ty = camel_case(self.cx, ident_to_string(rule));
+    let clash = self.generated_type_clash(ty).or_else(||
+      self.exprs.iter().find(|expr| match expr {
+        &&AstNode(_, other, _) => other == ty,
+        _ => false
+      }).map(|_| format!("another generated type")));
+    if let Some(clash) = clash {
+      self.span_err(self.find_rule_by_ident(rule).span(), format!(
+        "In rule {}: the type `{}` generated by `#![auto_box]` for this recursive rule clashes with {}.\n\
+         Help: rename the rule, or give its type with a semantic action.",
+        rule, ty, clash));
+      return false;
+    }
+    let this = self.expr_index_of_rule(rule);
+    let span = self[this].span;
+    let expr = self.exprs[this].clone();
+    self.exprs.push(expr);
+    self.exprs_info.push(ExprIType::infer(span));
+    let moved = self.exprs.len() - 1;
+    self.exprs[this] = AstNode(moved, ty, None);
+    true
+  }
+
+  pub fn struct_type(&self, name: Ident) -> IType {
+    IType::rust_type(quote_ty!(self.cx, $name))
+  }
+
   pub fn map_exprs_info(self, exprs_info: Vec<ExprType>) -> TGrammar<'a, 'b> {
     TGrammar {
       cx: self.cx,
@@ -75,26 +200,12 @@ impl<'a, 'b> IGrammar<'a, 'b>
       exprs: self.exprs,
       exprs_info: exprs_info,
       stream_alias: self.stream_alias,
+      generics: self.generics,
       rust_functions: self.rust_functions,
       rust_items: self.rust_items,
       attributes: self.attributes
     }
   }
-
-  /// We allocate a fake spanned expr so we can retrieve its type from `grammar.stream_type()`.
-  /// This is because the type of `SpannedExpr(e)` is `(stream_type, e)` but `stream_type` needs an expression index to fit in the type AST.
-  fn alloc_span_ty_expr(&mut self) {
-    self.exprs.push(Expression::SpannedExpr(0)); // fake, just to keep exprs and exprs_info consistent.
-    let span_ty = self.span_type();
-    self.exprs_info.push(
-      ExpressionInfo::new(span_ty.span.clone(),
-        IType::Regular(Type::Action(
-          rust::FunctionRetTy::Ty(span_ty)))));
-  }
-
-  pub fn span_ty_idx(&self) -> usize {
-    self.exprs_info.len() - 1
-  }
 }
 
 pub type ExprIType = ExpressionInfo<IType>;
@@ -129,9 +240,16 @@ impl<Ty> ExpressionInfo<Ty> where
   }
 }
 
-impl ExprType {
-  pub fn type_cardinality(&self) -> usize {
-    self.ty.cardinality()
+impl<'a, 'b> TGrammar<'a, 'b>
+{
+  /// The number of values produced by `expr_idx`, the tuples and spanned expressions being flattened.
+  pub fn cardinality(&self, expr_idx: usize) -> usize {
+    match self[expr_idx].ty {
+      Tuple(ref indexes) => indexes.iter().map(|idx| self.cardinality(*idx)).sum(),
+      Spanned(child) => 1 + self.cardinality(child),
+      Unit => 0,
+      _ => 1
+    }
   }
 }
 
@@ -250,6 +368,11 @@ pub enum IType
 
 impl IType
 {
+  /// The type of an expression annotated with a Rust type, such as in `e -> Box<Expr>`.
+  pub fn rust_type(ty: RTy) -> IType {
+    Regular(Action(rust::FunctionRetTy::Ty(ty)))
+  }
+
   pub fn rec(kind: RecKind, rec_path: Vec<Ident>) -> IType {
     let path = RecPath::new(kind, rec_path);
     Rec(RecSet::new(path))
@@ -285,7 +408,10 @@ pub enum Type
   Atom,
   Optional(usize),
   List(usize),
-  // Spanned(usize),
+  /// `Collection(i, ty)` is the type `ty` of a repetition of the expression `i` ascribed with `e* -> ty`, where `ty` implements `Default` and `Extend` (such as `String` or `HashMap<K, V>`).
+  Collection(usize, RTy),
+  /// `Spanned(i)` is the type of `..e` where `e` is the expression `i`: the span of the input consumed by `e` followed by the values of `e`.
+  Spanned(usize),
   /// `Tuple(vec![i,..,j])` is a tuple with the types of the sub-expressions at index `{i,..,j}`.
   /// Precondition: Tuple size >= 2.
   Tuple(Vec<usize>),
@@ -294,44 +420,30 @@ pub enum Type
 
 impl Type
 {
-  pub fn cardinality(&self) -> usize {
-    match *self {
-      Unit => 0,
-      Atom
-    | Optional(_)
-    | List(_)
-    | Action(_) => 1,
-    // | Spanned(_) => 1,
-      Tuple(ref indexes) => indexes.len()
+  /// The types of the semantic actions are not compared, the Rust compiler reports the mismatches.
+  pub fn syntactic_eq(&self, grammar: &IGrammar, other: &Type) -> bool {
+    match (self, other) {
+      (&Action(_), &Action(_)) => true,
+      _ => self.eq_by(other, |e1, e2| grammar.type_of(e1).syntactic_eq(grammar, &grammar.type_of(e2)))
     }
   }
 
-  pub fn syntactic_eq(&self, grammar: &IGrammar, other: &Type) -> bool {
-    let syntactic_eq_expr = |e1, e2| {
-      let ty1 = grammar.type_of(e1);
-      let ty2 = grammar.type_of(e2);
-      ty1.syntactic_eq(grammar, &ty2)
-    };
+  /// Compares the structure of the types, the types of the sub-expressions are compared with `expr_eq`.
+  pub fn eq_by<F>(&self, other: &Type, expr_eq: F) -> bool where
+   F: Fn(usize, usize) -> bool
+  {
     match (self.clone(), other.clone()) {
       (Unit, Unit) => true,
       (Atom, Atom) => true,
       (Optional(e1), Optional(e2))
-    | (List(e1), List(e2)) => syntactic_eq_expr(e1, e2),
-    // | (Spanned(e1), Spanned(e2)) => syntactic_eq_expr(e1, e2),
-      (Tuple(exprs1), Tuple(exprs2)) => {
-        if exprs1.len() == exprs2.len() {
-          for (e1, e2) in exprs1.into_iter().zip(exprs2.into_iter()) {
-            if !syntactic_eq_expr(e1, e2) {
-              return false;
-            }
-          }
-          true
-        }
-        else {
-          false
-        }
-      }
-      (Action(_), Action(_)) => true,
+    | (List(e1), List(e2))
+    | (Spanned(e1), Spanned(e2)) => expr_eq(e1, e2),
+      (Collection(_, ty1), Collection(_, ty2)) => ty1 == ty2,
+      (Tuple(exprs1), Tuple(exprs2)) => exprs1.len() == exprs2.len()
+        && exprs1.into_iter().zip(exprs2.into_iter()).all(|(e1, e2)| expr_eq(e1, e2)),
+      (Action(rust::FunctionRetTy::Default(_)), Action(rust::FunctionRetTy::Default(_))) => true,
+      (Action(rust::FunctionRetTy::Ty(ty1)), Action(rust::FunctionRetTy::Ty(ty2))) =>
+        rust::ty_to_string(&*ty1) == rust::ty_to_string(&*ty2),
       _ => false
     }
   }
@@ -339,13 +451,21 @@ impl Type
   pub fn display(&self, grammar: &IGrammar) -> String {
     match self.clone() {
       Unit => format!("()"),
-      Atom => format!("char"),
+      Atom => format!("<Stream as Iterator>::Item"),
       Optional(child) =>
         format!("Option<{}>", grammar.type_of(child).display(grammar)),
       List(child) =>
         format!("Vec<{}>", grammar.type_of(child).display(grammar)),
-      // Spanned(child) =>
-      //   format!("<todo>"), //(<Range<Stream> as StreamSpan>::Output, {})", grammar.type_of(child).display(grammar))
+      Collection(_, ty) => rust::ty_to_string(&*ty),
+      Spanned(child) => {
+        let span = "<Range<Stream> as StreamSpan>::Output";
+        match grammar.type_of(child) {
+          IType::Invisible
+        | Regular(Unit) => format!("{}", span),
+          Regular(Tuple(_)) => format!("({}, {}", span, &grammar.type_of(child).display(grammar)[1..]),
+          ty => format!("({}, {})", span, ty.display(grammar))
+        }
+      }
       Tuple(children) => {
         let mut display = format!("(");
         for child in children {
@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use rust;
 use middle::typing::ast::*;
 use middle::typing::type_rewriting::*;
 use middle::typing::ast::Type::*;
 use middle::typing::ast::IType::*;
 use middle::typing::surface::*;
 use middle::typing::typing_printer::*;
+use middle::typing::instantiation::Instantiation;
+use middle::analysis::ast::Lint;
 
 pub struct Depth<'a, 'b: 'a>
 {
@@ -28,10 +31,14 @@ pub struct Depth<'a, 'b: 'a>
 
 impl<'a, 'b> Depth<'a, 'b>
 {
-  pub fn infer(grammar: IGrammar<'a, 'b>) -> TGrammar<'a, 'b> {
+  pub fn infer(grammar: IGrammar<'a, 'b>) -> Partial<TGrammar<'a, 'b>> {
     let mut engine = Depth::new(grammar);
     engine.surface.surface();
-    engine.warn_recursive_type();
+    let mut has_error = false;
+    if engine.surface.grammar.attributes.auto_box {
+      has_error = engine.box_recursive_types();
+    }
+    let denied = engine.warn_recursive_type();
     engine.reduce_all_rec();
     engine.depth();
     engine.reduce_all_invisible();
@@ -40,7 +47,12 @@ impl<'a, 'b> Depth<'a, 'b>
       println!("After applying Depth.");
       print_debug(&grammar);
     }
-    grammar.map_exprs_info(engine.exprs_info)
+    if denied || has_error {
+      Partial::Nothing
+    }
+    else {
+      Partial::Value(grammar.map_exprs_info(engine.exprs_info))
+    }
   }
 
   fn new(grammar: IGrammar<'a, 'b>) -> Depth<'a, 'b> {
@@ -83,24 +95,205 @@ impl<'a, 'b> Depth<'a, 'b>
     self.surface.type_of(expr_idx)
   }
 
-  fn warn_recursive_type(&mut self) {
+  /// The value of `expr_idx` is not needed, for example when the values are passed by labels to an action.
+  fn visit_unit_expr(&mut self, expr_idx: usize) {
+    let old = self.under_unit;
+    self.under_unit = true;
+    self.visit_expr(expr_idx);
+    self.under_unit = old;
+  }
+
+  /// The value of `child` is converted with `Into` into the annotated type: it must produce a value, as many values as the annotated type if it is a tuple, and a value convertible into the annotated type when both are built from standard types.
+  fn check_rust_ascription(&self, this: usize, child: usize, rty: RTy) {
+    let grammar = &self.surface.grammar;
+    let produced = grammar.rust_types_of(child);
+    let expected = match rty.node {
+      rust::TyKind::Tup(ref tys) => Some(tys.len()),
+      _ => None
+    };
+    let mismatch = match (produced.as_ref().map(|tys| tys.len()), expected) {
+      (Some(0), _) => Some(format!("it produces no value")),
+      (Some(produced), Some(expected)) if produced != expected =>
+        Some(format!("it produces {} value(s)", produced)),
+      (Some(_), _) => {
+        let inferred = grammar.rust_type_of(child).unwrap();
+        if distinct_types(&inferred, &rty) && !from_conversion(&inferred, &rty) {
+          Some(format!("it produces a value of type `{}` which can not be converted into `{}`",
+            rust::ty_to_string(&*inferred), rust::ty_to_string(&*rty)))
+        }
+        else { None }
+      }
+      _ => None
+    };
+    if let Some(msg) = mismatch {
+      grammar.expr_err(this, format!(
+        "Type mismatch: the expression is annotated with `{}` but {}. \
+         The value of an annotated expression is converted into the annotated type with `Into`.",
+        rust::ty_to_string(&*rty), msg));
+    }
+  }
+
+  /// Compares the parameters of `action` with the values produced by `child`: their number must match, and their types when they are both built from standard types. It avoids an error inside the generated code.
+  fn check_action_signature(&self, this: usize, child: usize, action: Ident) {
+    let grammar = &self.surface.grammar;
+    let (inputs, output) = match grammar.rust_functions[&action].node {
+      rust::ItemKind::Fn(ref decl, ..) => (decl.inputs.clone(), decl.output.clone()),
+      _ => return
+    };
+    let args = match grammar.action_arg_types(child, action) {
+      Some(args) => args,
+      None => return
+    };
+    let mismatch =
+      if inputs.len() != args.len() {
+        Some(format!("the action `{}` expects {} argument(s) but the expression produces {} value(s)",
+          action, inputs.len(), args.len()))
+      }
+      else if grammar.action_generics(action).is_none() {
+        inputs.iter().zip(args.iter())
+          .position(|(param, arg)| distinct_types(&param.ty, arg))
+          .map(|i| format!("the parameter {} of the action `{}` has type `{}` but the expression produces a value of type `{}`",
+            i + 1, action, rust::ty_to_string(&*inputs[i].ty), rust::ty_to_string(&*args[i])))
+      }
+      else { None };
+    if let Some(msg) = mismatch {
+      let args: Vec<_> = args.iter().map(|arg| rust::ty_to_string(&*arg)).collect();
+      let output = match output {
+        rust::FunctionRetTy::Ty(ty) => format!(" -> {}", rust::ty_to_string(&*ty)),
+        rust::FunctionRetTy::Default(_) => String::new()
+      };
+      grammar.multi_locations_err(vec![
+        (grammar[this].span, format!("Type mismatch: {}.", msg)),
+        (grammar.rust_functions[&action].span, format!(
+          "The expected signature is `fn {}({}){}`.", action, args.join(", "), output))
+      ]);
+    }
+    else {
+      self.check_action_instantiation(this, child, action);
+    }
+  }
+
+  /// The type parameters of a generic action occurring in its output type must be inferred from its arguments, unless they are type parameters of the grammar, otherwise the type of the expression would mention a parameter unknown to the generated code.
+  fn check_action_instantiation(&self, this: usize, child: usize, action: Ident) {
+    let grammar = &self.surface.grammar;
+    let (instantiation, output) = match grammar.action_instantiation(child, action) {
+      Some(instantiation) => instantiation,
+      None => return
+    };
+    let grammar_params = Instantiation::new(&grammar.generics);
+    let unbound: Vec<_> = instantiation.unbound_params(&output).into_iter()
+      .filter(|param| !grammar_params.is_type_param(*param))
+      .map(|param| format!("`{}`", param))
+      .collect();
+    if !unbound.is_empty() {
+      grammar.multi_locations_err(vec![
+        (grammar[this].span, format!(
+          "The type parameter(s) {} of the action `{}` can not be inferred from the values of this expression.\n\
+           Help: use them in the types of the parameters of `{}`, or declare them as type parameters of the grammar.",
+          unbound.join(", "), action, action)),
+        (grammar.rust_functions[&action].span, format!("The action `{}` is declared here.", action))
+      ]);
+    }
+  }
+
+  /// When a sequence has labels, only the labelled values are given to the action or the structure: the values of the other elements are discarded.
+  fn warn_discarded_values(&self, child: usize, target: String) {
+    let grammar = &self.surface.grammar;
+    let elements = match grammar.exprs[child] {
+      Expression::Sequence(ref seq) => seq.clone(),
+      _ => return
+    };
+    for element in elements {
+      if let Expression::Binding(..) = grammar.exprs[element] {
+        continue;
+      }
+      match grammar.rust_types_of(element) {
+        Some(ref tys) if !tys.is_empty() => {
+          let tys: Vec<_> = tys.iter().map(|ty| rust::ty_to_string(&*ty)).collect();
+          grammar.span_warn(grammar[element].span, format!(
+            "The value of this expression (of type `{}`) is discarded: the sequence has labels \
+             so only the labelled values are given to {}.\n\
+             Help: label it, or annotate it with `-> (^)` if its value is not needed.",
+            tys.join(", "), target));
+        }
+        _ => ()
+      }
+    }
+  }
+
+  /// The nodes built by every level of a precedence expression are operands of the other levels, so the actions of the levels must return the type of the atom. Only the certain mismatches are reported, the types named differently might be aliases and are left to the Rust compiler.
+  fn check_precedence_types(&self, this: usize, atom: usize, levels: &Vec<PrecedenceLevel>) {
+    let grammar = &self.surface.grammar;
+    let atom_ty = match grammar.rust_type_of(atom) {
+      Some(ty) => ty,
+      None => return
+    };
+    for level in levels.iter().filter(|level| grammar.action_generics(level.action).is_none()) {
+      let output = match grammar.rust_functions[&level.action].node {
+        rust::ItemKind::Fn(ref decl, ..) => match decl.output {
+          rust::FunctionRetTy::Ty(ref ty) => ty.clone(),
+          rust::FunctionRetTy::Default(_) => quote_ty!(grammar.cx, ())
+        },
+        _ => continue
+      };
+      if distinct_types(&output, &atom_ty) {
+        grammar.multi_locations_err(vec![
+          (grammar[this].span, format!(
+            "Type mismatch: the action `{}` returns `{}` but the atom of the precedence expression \
+             produces a value of type `{}`. Every level builds an operand of the other levels, \
+             so the actions must return the type of the atom.",
+            level.action, rust::ty_to_string(&*output), rust::ty_to_string(&*atom_ty))),
+          (grammar[atom].span, format!("The atom of the precedence expression.")),
+          (grammar.rust_functions[&level.action].span, format!("The action `{}` is declared here.", level.action))
+        ]);
+      }
+    }
+  }
+
+  fn value_rec_set(&self) -> RecSet {
     let mut rec_set = RecSet::empty();
     for rule in self.surface.grammar.rules.clone() {
       if let Rec(r) = self.type_of(rule.expr_idx) {
         rec_set = rec_set.union(r);
       }
     }
-    rec_set = rec_set.remove_unit_kind();
-    if !rec_set.is_empty() {
-      let mut errors = vec![];
-      for rec_path in rec_set.path_set {
-        errors.push((
-          self.surface.grammar.find_rule_by_ident(rec_path.path[0]).span(),
-          format!("Infinite recursive type (type inferred: `(^)`): {}", rec_path.display())
-        ));
+    rec_set.remove_unit_kind()
+  }
+
+  /// `#![auto_box]`: the entry point of every recursive value type is wrapped into a type generated by `back::compiler::ast_types`, which boxes the fields leading back to it. The surface typing is then done again.
+  /// Returns `true` if a generated type clashes with another name.
+  fn box_recursive_types(&mut self) -> bool {
+    let mut entry_points = vec![];
+    for rec_path in self.value_rec_set().path_set {
+      if !entry_points.contains(&rec_path.path[0]) {
+        entry_points.push(rec_path.path[0]);
       }
-      self.surface.grammar.multi_locations_warn(errors);
     }
+    let mut has_error = false;
+    if !entry_points.is_empty() {
+      for rule in entry_points {
+        has_error |= !self.surface.grammar.wrap_rule_in_node(rule);
+      }
+      for expr_info in &mut self.surface.grammar.exprs_info {
+        expr_info.ty = Infer;
+      }
+      self.surface.surface();
+    }
+    has_error
+  }
+
+  /// Returns `true` if the lint `recursive_type` is denied on one of the recursive rules.
+  fn warn_recursive_type(&mut self) -> bool {
+    let grammar = &self.surface.grammar;
+    let mut denied = false;
+    for rec_path in self.value_rec_set().path_set {
+      let rule = rec_path.path[0];
+      denied |= grammar.lint(Lint::RecursiveType, Some(rule), vec![(
+        grammar.find_rule_by_ident(rule).span(),
+        format!("Infinite recursive type (type inferred: `(^)`): {}", rec_path.display())
+      )]);
+    }
+    denied
   }
 }
 
@@ -145,8 +338,11 @@ impl<'a, 'b> Visitor<()> for Depth<'a, 'b>
   unit_visitor_impl!(sequence);
   unit_visitor_impl!(choice);
 
-  fn visit_type_ascription(&mut self, _this: usize, child: usize, _ty: IType) {
+  fn visit_type_ascription(&mut self, this: usize, child: usize, ty: IType) {
     self.surface_expr(child);
+    if let Regular(Action(rust::FunctionRetTy::Ty(rty))) = ty {
+      self.check_rust_ascription(this, child, rty);
+    }
     self.visit_expr(child);
   }
 
@@ -155,8 +351,126 @@ impl<'a, 'b> Visitor<()> for Depth<'a, 'b>
     self.visit_expr(child);
   }
 
-  fn visit_semantic_action(&mut self, _this: usize, child: usize, _action: Ident) {
+  fn visit_semantic_action(&mut self, this: usize, child: usize, action: Ident) {
+    self.surface_expr(child);
+    // Under a unit type, the action is not called.
+    if !self.under_unit {
+      self.check_action_signature(this, child, action);
+    }
+    if self.surface.grammar.bound_labels(child).is_empty() {
+      self.visit_expr(child);
+    }
+    else {
+      if !self.under_unit {
+        self.warn_discarded_values(child, format!("the action `{}`", action));
+      }
+      self.visit_unit_expr(child);
+    }
+  }
+
+  fn visit_struct_action(&mut self, _this: usize, child: usize, name: Ident, _fields: Vec<Ident>) {
+    self.surface_expr(child);
+    if !self.under_unit {
+      self.warn_discarded_values(child, format!("the structure `{}`", name));
+    }
+    self.visit_unit_expr(child);
+  }
+
+  // The value of the labelled expression is needed by the label even if the binding is under a unit type.
+  fn visit_binding(&mut self, _this: usize, _label: Ident, child: usize) {
+    let old = self.under_unit;
+    self.under_unit = false;
+    self.surface_expr(child);
+    self.visit_expr(child);
+    self.under_unit = old;
+  }
+
+  fn visit_ast_node(&mut self, _this: usize, child: usize, _ty: Ident, _variant: Option<Ident>) {
     self.surface_expr(child);
     self.visit_expr(child);
   }
+
+  // Only the input consumed by `e` is captured so the value of `e` is never built.
+  fn visit_capture(&mut self, _this: usize, child: usize) {
+    self.surface_expr(child);
+    self.visit_unit_expr(child);
+  }
+
+  fn visit_precedence(&mut self, this: usize, atom: usize, levels: Vec<PrecedenceLevel>) {
+    if !self.under_unit {
+      self.check_precedence_types(this, atom, &levels);
+    }
+    for level in levels {
+      self.surface_expr(level.op);
+      self.visit_expr(level.op);
+    }
+    self.surface_expr(atom);
+    self.visit_expr(atom);
+  }
+}
+
+/// `true` if `ty1` and `ty2` are certainly different: they are built from tuples, references and standard types (such as `Vec` or `char`) that differ. Other types might be aliases so they are never considered as different.
+fn distinct_types(ty1: &RTy, ty2: &RTy) -> bool {
+  use rust::TyKind::*;
+  match (&ty1.node, &ty2.node) {
+    (&Paren(ref ty1), _) => distinct_types(ty1, ty2),
+    (_, &Paren(ref ty2)) => distinct_types(ty1, ty2),
+    (&Tup(ref tys1), &Tup(ref tys2)) => tys1.len() != tys2.len()
+      || tys1.iter().zip(tys2.iter()).any(|(ty1, ty2)| distinct_types(ty1, ty2)),
+    // Deref coercions apply between references, for example from `&String` to `&str`.
+    (&Rptr(..), &Rptr(..)) => false,
+    (&Path(None, ref p1), &Path(None, ref p2)) if is_std_type(p1) && is_std_type(p2) => {
+      let (s1, s2) = (&p1.segments[0], &p2.segments[0]);
+      let tys1 = Instantiation::angle_bracketed_types(s1);
+      let tys2 = Instantiation::angle_bracketed_types(s2);
+      s1.identifier != s2.identifier
+        || (tys1.len() == tys2.len()
+          && tys1.iter().zip(tys2.iter()).any(|(ty1, ty2)| distinct_types(ty1, ty2)))
+    }
+    (&Path(None, ref path), &Tup(_))
+  | (&Path(None, ref path), &Rptr(..))
+  | (&Tup(_), &Path(None, ref path))
+  | (&Rptr(..), &Path(None, ref path)) => is_std_type(path),
+    (&Tup(_), &Rptr(..))
+  | (&Rptr(..), &Tup(_)) => true,
+    _ => false
+  }
+}
+
+/// `true` if the standard library implements `From<ty1>` for `ty2`, for the conversions between distinct standard types: the lossless numeric conversions, from a character or a string to `String`, and from a value to `Option` or `Box` of this value.
+fn from_conversion(ty1: &RTy, ty2: &RTy) -> bool {
+  use rust::TyKind::*;
+  static LOSSLESS: [(&'static str, &'static str); 33] = [
+    ("u8", "u16"), ("u8", "u32"), ("u8", "u64"), ("u8", "usize"), ("u8", "i16"), ("u8", "i32"),
+    ("u8", "i64"), ("u8", "f32"), ("u8", "f64"), ("u8", "char"), ("u16", "u32"), ("u16", "u64"),
+    ("u16", "usize"), ("u16", "i32"), ("u16", "i64"), ("u16", "f32"), ("u16", "f64"), ("u32", "u64"),
+    ("u32", "i64"), ("u32", "f64"), ("i8", "i16"), ("i8", "i32"), ("i8", "i64"), ("i8", "isize"),
+    ("i8", "f32"), ("i8", "f64"), ("i16", "i32"), ("i16", "i64"), ("i16", "isize"), ("i16", "f32"),
+    ("i16", "f64"), ("i32", "i64"), ("i32", "f64")];
+  static FROM_CHAR: [&'static str; 3] = ["String", "u32", "u64"];
+  let name = |path: &rust::Path| path.segments[0].identifier.name.as_str().to_string();
+  match (&ty1.node, &ty2.node) {
+    (&Paren(ref ty1), _) => from_conversion(ty1, ty2),
+    (_, &Paren(ref ty2)) => from_conversion(ty1, ty2),
+    (&Path(None, ref from_path), &Path(None, ref into_path)) if is_std_type(from_path) && is_std_type(into_path) => {
+      let (from, into) = (name(from_path), name(into_path));
+      let wrapped = Instantiation::angle_bracketed_types(&into_path.segments[0]);
+      LOSSLESS.contains(&(&*from, &*into))
+      || (from == "char" && FROM_CHAR.contains(&&*into))
+      || ((into == "Option" || into == "Box") && wrapped.len() == 1 && !distinct_types(ty1, &wrapped[0]))
+    }
+    // `&str` is converted into `String`, `Box<str>` or `Vec<u8>`, and any value into `Option` or `Box`.
+    (&Rptr(..), &Path(None, ref path)) if is_std_type(path) =>
+      ["String", "Box", "Vec", "Option"].contains(&&*name(path)),
+    (&Tup(_), &Path(None, ref path)) if is_std_type(path) =>
+      ["Box", "Option"].contains(&&*name(path)),
+    _ => false
+  }
+}
+
+fn is_std_type(path: &rust::Path) -> bool {
+  static STD_TYPES: [&'static str; 19] = ["Vec", "Option", "String", "Box", "str", "char", "bool",
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize", "f32", "f64"];
+  path.segments.len() == 1 &&
+  STD_TYPES.contains(&&*path.segments[0].identifier.name.as_str())
 }
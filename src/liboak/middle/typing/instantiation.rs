@@ -0,0 +1,164 @@
+// Copyright 2016 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instantiation of the type parameters of a generic semantic action. For example, with `fn pair<T>(x: T, y: T) -> (T, T)` and an argument of type `char`, the type of `e > pair` is `(char, char)`. The type parameters are matched syntactically against the argument types, so they are only instantiated when they appear in the parameter types as a path (`T`, `Vec<T>`), a tuple, a slice or a reference.
+
+use middle::typing::ast::*;
+use rust;
+use rust::TyKind;
+use std::collections::HashMap;
+
+pub struct Instantiation
+{
+  type_params: Vec<Ident>,
+  bindings: HashMap<Ident, RTy>
+}
+
+impl Instantiation
+{
+  pub fn new(generics: &rust::Generics) -> Self {
+    let type_params = generics.params.iter()
+      .filter_map(|param| match param {
+        &rust::GenericParam::Type(ref ty) => Some(ty.ident),
+        _ => None
+      })
+      .collect();
+    Instantiation {
+      type_params: type_params,
+      bindings: HashMap::new()
+    }
+  }
+
+  pub fn is_generic(&self) -> bool {
+    !self.type_params.is_empty()
+  }
+
+  pub fn is_type_param(&self, ident: Ident) -> bool {
+    self.type_params.contains(&ident)
+  }
+
+  /// Binds the type parameters occurring in `param` to the corresponding types in `arg`. The first binding of a parameter is kept, the Rust compiler reports the conflicting ones.
+  pub fn unify(&mut self, param: &RTy, arg: &RTy) {
+    match (&param.node, &arg.node) {
+      (&TyKind::Path(None, ref path), _) if self.type_param(path).is_some() => {
+        let ident = self.type_param(path).unwrap();
+        self.bindings.entry(ident).or_insert(arg.clone());
+      }
+      (&TyKind::Path(None, ref p1), &TyKind::Path(_, ref p2)) => {
+        let s1 = p1.segments.last().unwrap();
+        let s2 = p2.segments.last().unwrap();
+        if s1.identifier == s2.identifier {
+          let tys1 = Instantiation::angle_bracketed_types(s1);
+          let tys2 = Instantiation::angle_bracketed_types(s2);
+          if tys1.len() == tys2.len() {
+            for (ty1, ty2) in tys1.iter().zip(tys2.iter()) {
+              self.unify(ty1, ty2);
+            }
+          }
+        }
+      }
+      (&TyKind::Tup(ref tys1), &TyKind::Tup(ref tys2)) if tys1.len() == tys2.len() => {
+        for (ty1, ty2) in tys1.iter().zip(tys2.iter()) {
+          self.unify(ty1, ty2);
+        }
+      }
+      (&TyKind::Slice(ref ty1), &TyKind::Slice(ref ty2)) => self.unify(ty1, ty2),
+      (&TyKind::Rptr(_, ref mt1), &TyKind::Rptr(_, ref mt2)) => self.unify(&mt1.ty, &mt2.ty),
+      (&TyKind::Paren(ref ty1), _) => self.unify(ty1, arg),
+      _ => ()
+    }
+  }
+
+  /// `true` if every type parameter occurring in `ty` is bound.
+  pub fn is_instantiated(&self, ty: &RTy) -> bool {
+    self.unbound_params(ty).is_empty()
+  }
+
+  /// The type parameters occurring in `ty` that are not bound.
+  pub fn unbound_params(&self, ty: &RTy) -> Vec<Ident> {
+    match ty.node {
+      TyKind::Path(None, ref path) => {
+        match self.type_param(path) {
+          Some(ident) if self.bindings.contains_key(&ident) => vec![],
+          Some(ident) => vec![ident],
+          None => path.segments.iter()
+            .flat_map(|s| Instantiation::angle_bracketed_types(s))
+            .flat_map(|ty| self.unbound_params(&ty))
+            .collect()
+        }
+      }
+      TyKind::Tup(ref tys) => tys.iter().flat_map(|ty| self.unbound_params(ty)).collect(),
+      TyKind::Slice(ref ty)
+    | TyKind::Array(ref ty, _)
+    | TyKind::Paren(ref ty) => self.unbound_params(ty),
+      TyKind::Rptr(_, ref mt) => self.unbound_params(&mt.ty),
+      _ => vec![]
+    }
+  }
+
+  /// Replaces the type parameters in `ty` with the types they are bound to.
+  pub fn substitute(&self, ty: RTy) -> RTy {
+    let node = match ty.node.clone() {
+      TyKind::Path(None, ref path) if self.type_param(path).is_some() => {
+        return self.bindings[&self.type_param(path).unwrap()].clone();
+      }
+      TyKind::Path(qself, mut path) => {
+        for segment in path.segments.iter_mut() {
+          if let Some(ref mut parameters) = segment.parameters {
+            if let rust::PathParameters::AngleBracketed(ref mut data) = **parameters {
+              data.types = data.types.iter().cloned()
+                .map(|ty| self.substitute(ty))
+                .collect();
+            }
+          }
+        }
+        TyKind::Path(qself, path)
+      }
+      TyKind::Tup(tys) => TyKind::Tup(tys.into_iter().map(|ty| self.substitute(ty)).collect()),
+      TyKind::Slice(ty) => TyKind::Slice(self.substitute(ty)),
+      TyKind::Array(ty, len) => TyKind::Array(self.substitute(ty), len),
+      TyKind::Paren(ty) => TyKind::Paren(self.substitute(ty)),
+      TyKind::Rptr(lifetime, mt) => TyKind::Rptr(lifetime, rust::MutTy {
+        ty: self.substitute(mt.ty),
+        mutbl: mt.mutbl
+      }),
+      node => node
+    };
+    rust::P(rust::Ty {
+      id: ty.id,
+      node: node,
+      span: ty.span
+    })
+  }
+
+  fn type_param(&self, path: &rust::Path) -> Option<Ident> {
+    if path.segments.len() == 1 && path.segments[0].parameters.is_none() {
+      let ident = path.segments[0].identifier;
+      self.type_params.iter().find(|param| **param == ident).cloned()
+    }
+    else {
+      None
+    }
+  }
+
+  pub fn angle_bracketed_types(segment: &rust::PathSegment) -> Vec<RTy> {
+    match segment.parameters {
+      Some(ref parameters) => match **parameters {
+        rust::PathParameters::AngleBracketed(ref data) => data.types.clone(),
+        _ => vec![]
+      },
+      None => vec![]
+    }
+  }
+}
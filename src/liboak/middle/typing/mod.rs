@@ -20,9 +20,10 @@ pub mod ast;
 mod surface;
 mod depth;
 mod type_rewriting;
+pub mod instantiation;
 mod typing_printer;
 
 pub fn type_inference<'a, 'b>(agrammar: AGrammar<'a, 'b>) -> Partial<TGrammar<'a, 'b>> {
   let grammar = IGrammar::from(agrammar);
-  Partial::Value(Depth::infer(grammar))
+  Depth::infer(grammar)
 }
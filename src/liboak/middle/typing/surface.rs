@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use rust;
 use middle::typing::ast::*;
 use middle::typing::ast::IType::*;
 use middle::typing::type_rewriting::*;
@@ -135,6 +136,10 @@ impl<'a, 'b> Visitor<IType> for Surface<'a, 'b>
     IType::Invisible
   }
 
+  fn visit_byte_literal(&mut self, _this: usize, _bytes: Vec<u8>) -> IType {
+    IType::Invisible
+  }
+
   fn visit_syntactic_predicate(&mut self, _this: usize, _child: usize) -> IType {
     IType::Invisible
   }
@@ -143,12 +148,50 @@ impl<'a, 'b> Visitor<IType> for Surface<'a, 'b>
     ty
   }
 
+  // The value of `label:e` is bound to `label`, so it does not appear in the value of the enclosing expression.
+  fn visit_binding(&mut self, _this: usize, _label: Ident, _child: usize) -> IType {
+    IType::Invisible
+  }
+
+  fn visit_ast_node(&mut self, _this: usize, _child: usize, ty: Ident, _variant: Option<Ident>) -> IType {
+    self.grammar.struct_type(ty)
+  }
+
+  fn visit_capture(&mut self, _this: usize, _child: usize) -> IType {
+    IType::Regular(Type::Action(
+      rust::FunctionRetTy::Ty(self.grammar.slice_type())))
+  }
+
   fn visit_atom(&mut self, _this: usize) -> IType {
     IType::Regular(Type::Atom)
   }
 
-  fn visit_semantic_action(&mut self, this: usize, _child: usize, action: Ident) -> IType {
-    self.grammar.action_type(this, action)
+  fn visit_indentation(&mut self, _this: usize, _kind: IndentKind) -> IType {
+    IType::Invisible
+  }
+
+  // The arguments of a generic action are typed first so its type parameters can be instantiated.
+  fn visit_semantic_action(&mut self, this: usize, child: usize, action: Ident) -> IType {
+    if self.grammar.action_generics(action).is_some() {
+      self.visit_expr(child);
+      for label in self.grammar.bound_labels(child) {
+        let labelled = self.grammar.labelled_expr(child, label).unwrap();
+        self.visit_expr(labelled);
+      }
+      self.grammar.instantiated_action_type(this, child, action)
+    }
+    else {
+      self.grammar.action_type(this, action)
+    }
+  }
+
+  fn visit_struct_action(&mut self, _this: usize, _child: usize, name: Ident, _fields: Vec<Ident>) -> IType {
+    self.grammar.struct_type(name)
+  }
+
+  // Every operator level builds a node of the same type (checked by `Depth`), so the first action gives the type of the whole expression.
+  fn visit_precedence(&mut self, this: usize, _atom: usize, levels: Vec<PrecedenceLevel>) -> IType {
+    self.grammar.action_type(this, levels[0].action)
   }
 
   // Inductive rules
@@ -169,7 +212,7 @@ impl<'a, 'b> Visitor<IType> for Surface<'a, 'b>
 
   fn visit_spanned_expr(&mut self, _this: usize, child: usize) -> IType {
     self.visit_expr(child);
-    IType::Regular(Type::Tuple(vec![self.grammar.span_ty_idx(), child]))
+    IType::Regular(Type::Spanned(child))
   }
 
   fn visit_sequence(&mut self, _this: usize, children: Vec<usize>) -> IType {
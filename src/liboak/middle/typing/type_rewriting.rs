@@ -58,6 +58,7 @@ impl TypeRewriting
     match ty.clone() {
       Optional(child)
     | List(child) => TypeRewriting::reduce_unary(grammar, child, Regular(ty)),
+      Spanned(child) => TypeRewriting::reduce_spanned(grammar, child, Regular(ty)),
       Tuple(indexes) => TypeRewriting::reduce_tuple(grammar, indexes),
       ty => Regular(ty)
     }
@@ -72,6 +73,14 @@ impl TypeRewriting
     }
   }
 
+  // The span is always produced, even if `expr_idx` does not produce a value.
+  fn reduce_spanned(grammar: &IGrammar, expr_idx: usize, ty: IType) -> IType {
+    match grammar.type_of(expr_idx) {
+      Rec(r) => Rec(r.to_value_kind()),
+      _ => ty
+    }
+  }
+
   fn reduce_tuple(grammar: &IGrammar, mut indexes: Vec<usize>) -> IType {
     assert!(indexes.len() > 0,
       "Empty tuple are forbidden: unit type must be represented with `Type::Unit`.");
@@ -15,6 +15,7 @@
 pub use syntax::ptr::P;
 pub use syntax::ast;
 pub use syntax::ast::*;
+pub use syntax::visit;
 pub use syntax::print::pprust::*;
 pub use syntax::print::pp;
 pub use syntax::util::small_vector::SmallVector;
@@ -25,6 +26,7 @@ pub use syntax::ext::build::AstBuilder;
 pub use syntax::ext::base::SyntaxExtension;
 
 pub use syntax::parse::str_lit;
+pub use syntax::parse::byte_str_lit;
 pub use syntax::parse::parser::Parser;
 pub use syntax::parse::ParseSess;
 pub use syntax::parse::PResult;
@@ -27,6 +27,14 @@ pub trait Visitor<R> : ExprByIndex
   fn visit_non_terminal_symbol(&mut self, _this: usize, _rule: Ident) -> R;
   fn visit_atom(&mut self, _this: usize) -> R;
 
+  fn visit_byte_literal(&mut self, this: usize, _bytes: Vec<u8>) -> R {
+    self.visit_atom(this)
+  }
+
+  fn visit_token_pattern(&mut self, this: usize, _pattern: RPat) -> R {
+    self.visit_atom(this)
+  }
+
   fn visit_any_single_char(&mut self, this: usize) -> R {
     self.visit_atom(this)
   }
@@ -54,6 +62,10 @@ pub trait Visitor<R> : ExprByIndex
     self.visit_repeat(this, child)
   }
 
+  fn visit_counted_repeat(&mut self, this: usize, child: usize, _count: Ident) -> R {
+    self.visit_repeat(this, child)
+  }
+
   fn visit_optional(&mut self, _this: usize, child: usize) -> R {
     self.visit_expr(child)
   }
@@ -74,9 +86,36 @@ pub trait Visitor<R> : ExprByIndex
     self.visit_expr(child)
   }
 
+  fn visit_struct_action(&mut self, _this: usize, child: usize, _name: Ident, _fields: Vec<Ident>) -> R {
+    self.visit_expr(child)
+  }
+
   fn visit_type_ascription(&mut self, _this: usize, child: usize, _ty: IType) -> R {
     self.visit_expr(child)
   }
+
+  fn visit_binding(&mut self, _this: usize, _label: Ident, child: usize) -> R {
+    self.visit_expr(child)
+  }
+
+  fn visit_ast_node(&mut self, _this: usize, child: usize, _ty: Ident, _variant: Option<Ident>) -> R {
+    self.visit_expr(child)
+  }
+
+  fn visit_capture(&mut self, _this: usize, child: usize) -> R {
+    self.visit_expr(child)
+  }
+
+  fn visit_indentation(&mut self, this: usize, _kind: IndentKind) -> R {
+    self.visit_atom(this)
+  }
+
+  fn visit_precedence(&mut self, _this: usize, atom: usize, levels: Vec<PrecedenceLevel>) -> R {
+    for level in levels {
+      self.visit_expr(level.op);
+    }
+    self.visit_expr(atom)
+  }
 }
 
 /// We need this macro for factorizing the code since we can not specialize a trait on specific type parameter (we would need to specialize on `()` here).
@@ -105,6 +144,12 @@ pub fn walk_expr<R, V: ?Sized>(visitor: &mut V, this: usize) -> R where
     StrLiteral(lit) => {
       visitor.visit_str_literal(this, lit)
     }
+    ByteLiteral(bytes) => {
+      visitor.visit_byte_literal(this, bytes)
+    }
+    TokenPattern(pattern) => {
+      visitor.visit_token_pattern(this, pattern)
+    }
     AnySingleChar => {
       visitor.visit_any_single_char(this)
     }
@@ -123,6 +168,9 @@ pub fn walk_expr<R, V: ?Sized>(visitor: &mut V, this: usize) -> R where
     OneOrMore(child) => {
       visitor.visit_one_or_more(this, child)
     }
+    CountedRepeat(child, count) => {
+      visitor.visit_counted_repeat(this, child, count)
+    }
     ZeroOrOne(child) => {
       visitor.visit_optional(this, child)
     }
@@ -138,12 +186,30 @@ pub fn walk_expr<R, V: ?Sized>(visitor: &mut V, this: usize) -> R where
     SemanticAction(child, action) => {
       visitor.visit_semantic_action(this, child, action)
     }
+    StructAction(child, name, fields) => {
+      visitor.visit_struct_action(this, child, name, fields)
+    }
     TypeAscription(child, ty) => {
       visitor.visit_type_ascription(this, child, ty)
     }
     SpannedExpr(child) => {
       visitor.visit_spanned_expr(this, child)
     }
+    Binding(label, child) => {
+      visitor.visit_binding(this, label, child)
+    }
+    AstNode(child, ty, variant) => {
+      visitor.visit_ast_node(this, child, ty, variant)
+    }
+    Capture(child) => {
+      visitor.visit_capture(this, child)
+    }
+    Precedence(atom, levels) => {
+      visitor.visit_precedence(this, atom, levels)
+    }
+    Indentation(kind) => {
+      visitor.visit_indentation(this, kind)
+    }
   }
 }
 
@@ -0,0 +1,45 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::auto_box::*;
+
+grammar! auto_box {
+  #![auto_box(Debug, PartialEq)]
+
+  list = number ("," list)?
+
+  ab = ["ab"] cd?
+  cd = ["cd"] ab?
+
+  number = ["0-9"]+ > to_number
+
+  use std::str::FromStr;
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    u32::from_str(&*raw_text.into_iter().collect::<String>()).unwrap()
+  }
+}
+
+#[test]
+fn test_auto_box() {
+  use oak_runtime::*;
+  use self::auto_box::*;
+
+  assert_eq!(parse_list("1,2".into_state()).unwrap_data(),
+    List(1, Some(Box::new(List(2, None)))));
+  assert_eq!(parse_ab("ac".into_state()).unwrap_data(),
+    Ab('a', Box::new(Some(('c', None)))));
+  assert_eq!(parse_cd("db".into_state()).unwrap_data(),
+    ('d', Some(Ab('b', Box::new(None)))));
+}
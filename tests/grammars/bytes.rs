@@ -0,0 +1,55 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::bytes::*;
+
+grammar! bytes {
+  type Stream<'a> = ByteStream<'a>;
+
+  message = b"\x7fOAK" version control* . > make_message
+
+  version = [b"\x01-\x03"]
+
+  control = [b"\x00-\x1f"]
+
+  use oak_runtime::byte_stream::*;
+
+  pub struct Message {
+    pub version: u8,
+    pub controls: Vec<u8>,
+    pub last: u8
+  }
+
+  fn make_message(version: u8, controls: Vec<u8>, last: u8) -> Message {
+    Message {
+      version: version,
+      controls: controls,
+      last: last
+    }
+  }
+}
+
+#[test]
+fn test_bytes() {
+  use oak_runtime::*;
+
+  let data: &[u8] = b"\x7fOAK\x02\x00\x1f\xff";
+  let message = bytes::parse_message(data.into_state()).unwrap_data();
+  assert_eq!(message.version, 2);
+  assert_eq!(message.controls, vec![0x00, 0x1f]);
+  assert_eq!(message.last, 0xff);
+
+  let data: &[u8] = b"\x7fOAK\x04";
+  assert!(bytes::parse_message(data.into_state()).is_failed());
+}
@@ -24,13 +24,11 @@ grammar! calc {
   program = spacing expression
 
   expression
-    = term (term_op term)* > fold_left
-
-  term
-    = exponent (factor_op exponent)* > fold_left
-
-  exponent
-    = (factor exponent_op)* factor > fold_right
+    = precedence factor {
+        left term_op > binary_expr,
+        left factor_op > binary_expr,
+        right exponent_op > binary_expr
+      }
 
   factor
     = number > number_expr
@@ -108,14 +106,8 @@ grammar! calc {
     raw_text.into_iter().collect()
   }
 
-  fn fold_left(head: PExpr, rest: Vec<(BinOp, PExpr)>) -> PExpr {
-    rest.into_iter().fold(head,
-      |accu, (op, expr)| Box::new(BinaryExpr(op, accu, expr)))
-  }
-
-  fn fold_right(front: Vec<(PExpr, BinOp)>, last: PExpr) -> PExpr {
-    front.into_iter().rev().fold(last,
-      |accu, (expr, op)| Box::new(BinaryExpr(op, expr, accu)))
+  fn binary_expr(lhs: PExpr, op: BinOp, rhs: PExpr) -> PExpr {
+    Box::new(BinaryExpr(op, lhs, rhs))
   }
 
   fn let_in_expr(var: String, value: PExpr, expr: PExpr) -> PExpr {
@@ -0,0 +1,42 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::capture::*;
+
+grammar! capture {
+  assignment = identifier spacing "=" spacing $number > make_assignment
+
+  identifier = $(["a-zA-Z_"] ["a-zA-Z0-9_"]*)
+
+  number = ["0-9"]+
+
+  spacing = " "* -> (^)
+
+  use std::str::FromStr;
+
+  fn make_assignment<'a>(var: &'a str, value: &'a str) -> (String, u32) {
+    (String::from(var), u32::from_str(value).unwrap())
+  }
+}
+
+#[test]
+fn test_capture() {
+  use oak_runtime::*;
+
+  let input = "x_1 = 42";
+  assert_eq!(capture::parse_identifier(input.into_state()).unwrap_data(), "x_1");
+  let state = capture::parse_assignment(input.into_state());
+  assert_eq!(state.unwrap_data(), (String::from("x_1"), 42));
+  assert!(capture::parse_identifier("1x".into_state()).is_failed());
+}
@@ -0,0 +1,42 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::char_class::*;
+
+grammar! char_class {
+  #![deny(char_class_overlap, single_char_class)]
+
+  identifier = ident_start ident_char* spacing
+
+  // ident_start = ["z-a"] // is detected: the interval is empty.
+  // ident_start = ["a-zA-Zf-m_"] // is detected: `f-m` overlaps `a-z`.
+  // ident_start = ["a-z_A-Z_"] // is detected: `_` is already in the class.
+  ident_start = ["a-zA-Z_"]
+
+  ident_char = ["a-zA-Z0-9_\x2d"]
+
+  // spacing = [" "]* -> (^) // is detected: equivalent to `" "*`.
+  spacing = [" \t"]* -> (^)
+
+  // The value of the class is used: not reported.
+  sign = ["-"]
+}
+
+#[test]
+fn test_char_class() {
+  use oak_runtime::*;
+
+  assert!(char_class::recognize_identifier("snake_case-id  ".into_state()).is_successful());
+  assert_eq!(char_class::parse_sign("-".into_state()).unwrap_data(), '-');
+}
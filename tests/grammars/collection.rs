@@ -0,0 +1,52 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::collection::*;
+
+grammar! collection {
+  dict = entry* -> HashMap<String, u32>
+
+  entry = word "=" number ";"
+
+  word = ["a-z"]+ -> String
+
+  number = ["0-9"]+ > to_number
+
+  letters = ["a-z"]* -> BTreeSet<char>
+
+  use std::collections::{HashMap, BTreeSet};
+  use std::str::FromStr;
+
+  fn to_number(digits: Vec<char>) -> u32 {
+    u32::from_str(&*digits.into_iter().collect::<String>()).unwrap()
+  }
+}
+
+#[test]
+fn test_collection() {
+  use oak_runtime::*;
+  use std::collections::{HashMap, BTreeSet};
+
+  assert_eq!(collection::parse_word("oak".into_state()).unwrap_data(), String::from("oak"));
+
+  let dict = collection::parse_dict("a=1;bc=23;".into_state()).unwrap_data();
+  let mut expected = HashMap::new();
+  expected.insert(String::from("a"), 1);
+  expected.insert(String::from("bc"), 23);
+  assert_eq!(dict, expected);
+
+  let letters = collection::parse_letters("abba".into_state()).unwrap_data();
+  let expected: BTreeSet<char> = "ab".chars().collect();
+  assert_eq!(letters, expected);
+}
@@ -0,0 +1,41 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::counted_repeat::*;
+
+grammar! counted_repeat {
+  netstring = n:length ":" .{n} "," > make_string
+
+  length = ["0-9"]+ > to_number
+
+  fn make_string(chars: Vec<char>) -> String {
+    chars.into_iter().collect()
+  }
+
+  fn to_number(digits: Vec<char>) -> usize {
+    digits.into_iter().fold(0, |n, d| n * 10 + d.to_digit(10).unwrap() as usize)
+  }
+}
+
+#[test]
+fn test_counted_repeat() {
+  use oak_runtime::*;
+
+  let state = counted_repeat::parse_netstring("5:a,b:c,".into_state());
+  assert_eq!(state.unwrap_data(), String::from("a,b:c"));
+  let state = counted_repeat::parse_netstring("0:,".into_state());
+  assert_eq!(state.unwrap_data(), String::from(""));
+  assert!(counted_repeat::parse_netstring("3:ab,".into_state()).is_failed());
+  assert!(counted_repeat::parse_netstring("1:ab,".into_state()).is_failed());
+}
@@ -0,0 +1,52 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::derive_ast::*;
+
+grammar! derive_ast {
+  #![derive_ast(Debug, Clone, PartialEq)]
+
+  expr
+    = number
+    / "(" expr ")"
+    / "-" expr
+
+  sum = number "+" number
+
+  pair = ["a-z"] "=" number
+
+  boolean
+    = "true"
+    / "false"
+
+  number = ["0-9"]+ > to_number
+
+  use std::str::FromStr;
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    u32::from_str(&*raw_text.into_iter().collect::<String>()).unwrap()
+  }
+}
+
+#[test]
+fn test_derive_ast() {
+  use oak_runtime::*;
+  use self::derive_ast::*;
+
+  assert_eq!(parse_sum("1+2".into_state()).unwrap_data(), Sum(1, 2));
+  assert_eq!(parse_pair("x=3".into_state()).unwrap_data(), Pair('x', 3));
+  assert_eq!(parse_boolean("false".into_state()).unwrap_data(), Boolean::False);
+  assert_eq!(parse_expr("-(4)".into_state()).unwrap_data(),
+    Expr::Alt3(Box::new(Expr::Alt2(Box::new(Expr::Number(4))))));
+}
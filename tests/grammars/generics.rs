@@ -0,0 +1,57 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::generics::*;
+
+grammar! generics {
+  <T: FromStr>
+
+  list = value ("," value)* > cons
+
+  value = $["0-9"]+ > from_str
+
+  twin = ["a-z"] > dup
+
+  twins = ["a-z"]+ > dup
+
+  use std::str::FromStr;
+
+  fn cons<A>(head: A, mut rest: Vec<A>) -> Vec<A> {
+    rest.insert(0, head);
+    rest
+  }
+
+  // `T` can not be inferred from the arguments, it refers to the parameter of the grammar.
+  fn from_str<T: FromStr>(text: &str) -> T {
+    T::from_str(text).ok().expect("digits")
+  }
+
+  fn dup<A: Clone>(x: A) -> (A, A) {
+    (x.clone(), x)
+  }
+}
+
+#[test]
+fn test_generics() {
+  use oak_runtime::*;
+
+  let list: Vec<u32> = generics::parse_list("1,2,3".into_state()).unwrap_data();
+  assert_eq!(list, vec![1, 2, 3]);
+  let list: Vec<u64> = generics::parse_list("10".into_state()).unwrap_data();
+  assert_eq!(list, vec![10]);
+  let twin = generics::parse_twin("a".into_state()).unwrap_data();
+  assert_eq!(twin, ('a', 'a'));
+  let twins = generics::parse_twins("ab".into_state()).unwrap_data();
+  assert_eq!(twins, (vec!['a', 'b'], vec!['a', 'b']));
+}
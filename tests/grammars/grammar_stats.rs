@@ -0,0 +1,39 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::grammar_stats::*;
+
+grammar! grammar_stats {
+  #![grammar_stats]
+
+  // LL(1), recursive through `value`.
+  list = "[" (value ("," value)*)? "]"
+
+  // LL(2): `"ab"` and `"ac"` start with the same symbol.
+  value = "ab" / "ac" / list / number
+
+  // Nullable.
+  sign = "-"?
+
+  number = sign ["0-9"]+
+}
+
+#[test]
+fn test_grammar_stats() {
+  use oak_runtime::*;
+
+  assert!(grammar_stats::recognize_list("[ab,[ac],-12]".into_state()).is_successful());
+  assert!(grammar_stats::recognize_list("[]".into_state()).is_successful());
+  assert!(grammar_stats::recognize_value("ad".into_state()).is_failed());
+}
@@ -0,0 +1,62 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::indentation::*;
+
+grammar! indentation {
+  forest = node (samedent node)* > cons
+
+  node = name "\n" children? > make_node
+
+  children = indent node (samedent node)* dedent > cons
+
+  name = ["a-z"]+ > to_string
+
+  pub struct Node {
+    pub name: String,
+    pub children: Vec<Node>
+  }
+
+  fn make_node(name: String, children: Option<Vec<Node>>) -> Node {
+    Node {
+      name: name,
+      children: children.unwrap_or(vec![])
+    }
+  }
+
+  fn cons(first: Node, rest: Vec<Node>) -> Vec<Node> {
+    let mut nodes = vec![first];
+    nodes.extend(rest.into_iter());
+    nodes
+  }
+
+  fn to_string(raw_text: Vec<char>) -> String {
+    raw_text.into_iter().collect()
+  }
+}
+
+#[test]
+fn test_indentation() {
+  use oak_runtime::*;
+
+  let state = indentation::parse_forest("a\n  b\n  c\n    d\ne\n".into_state());
+  let forest = state.unwrap_data();
+  assert_eq!(forest.len(), 2);
+  assert_eq!(forest[0].name, "a");
+  assert_eq!(forest[0].children.len(), 2);
+  assert_eq!(forest[0].children[1].name, "c");
+  assert_eq!(forest[0].children[1].children[0].name, "d");
+  assert_eq!(forest[1].name, "e");
+  assert!(forest[1].children.is_empty());
+}
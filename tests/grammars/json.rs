@@ -36,17 +36,12 @@ grammar! json {
     spacing = [" \n\r\t"]* -> (^)
     digit = ["0-9"]
     colon = ":" spacing
-    semicolon = ";" spacing
     coma = "," spacing
     number = digit+ spacing > to_number
-    not_zero_digit = ["1-9"]
-    digits = digit+
 
     json_char
     = ["a-zA-Z0-9_() ,.:&'/@?*=;-"]
 
-    lparen = "(" spacing
-    rparen = ")" spacing
     lbracket = "[" spacing
     rbracket = "]" spacing
     lbrace = "{" spacing
@@ -0,0 +1,61 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::labels::*;
+
+grammar! labels {
+  #![skip(spacing)]
+
+  let_binding = "let" name:identifier "=" value:number > Binding { name, value }
+
+  // The arguments are passed by name, so they can be declared in any order.
+  range = lo:number ".." hi:number > make_range
+
+  #[atomic]
+  identifier = ["a-z"]+ > to_string
+
+  #[atomic]
+  number = ["0-9"]+ > to_number
+
+  spacing = " "* -> (^)
+
+  use std::str::FromStr;
+
+  #[derive(Debug, PartialEq)]
+  pub struct Binding {
+    pub name: String,
+    pub value: u32
+  }
+
+  fn make_range(hi: u32, lo: u32) -> (u32, u32) {
+    (lo, hi)
+  }
+
+  fn to_string(raw_text: Vec<char>) -> String {
+    raw_text.into_iter().collect()
+  }
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    u32::from_str(&*to_string(raw_text)).unwrap()
+  }
+}
+
+#[test]
+fn test_labels() {
+  use oak_runtime::*;
+
+  let binding = labels::parse_let_binding("let x = 42".into_state()).unwrap_data();
+  assert_eq!(binding, labels::Binding { name: String::from("x"), value: 42 });
+  assert_eq!(labels::parse_range("1 .. 5".into_state()).unwrap_data(), (1, 5));
+}
@@ -0,0 +1,53 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::left_factoring::*;
+
+grammar! left_factoring {
+  #![skip(spacing)]
+
+  // The factored forms suggested for the commented rules.
+
+  // assignment = "let" identifier "=" number
+  //            / "let" identifier ";"
+  //            / "const" identifier "=" number // is detected
+  assignment = "let" identifier ("=" number / ";")
+             / "const" identifier "=" number
+
+  // call = identifier "(" ")" / identifier // is detected
+  call = identifier ("(" ")")?
+
+  // The actions receive different values once factored: no suggestion is given.
+  // value = number "." number > make_float
+  //       / number > make_int // is detected
+
+  #[atomic]
+  identifier = ["a-z"]+ -> (^)
+
+  #[atomic]
+  number = ["0-9"]+ -> (^)
+
+  spacing = " "* -> (^)
+}
+
+#[test]
+fn test_left_factoring() {
+  use oak_runtime::*;
+
+  assert!(left_factoring::recognize_assignment("let x = 1".into_state()).is_successful());
+  assert!(left_factoring::recognize_assignment("let x ;".into_state()).is_successful());
+  assert!(left_factoring::recognize_assignment("const x = 1".into_state()).is_successful());
+  assert!(left_factoring::recognize_call("f ( )".into_state()).is_successful());
+  assert!(left_factoring::recognize_call("f".into_state()).is_successful());
+}
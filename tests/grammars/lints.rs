@@ -0,0 +1,50 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::lints::*;
+
+grammar! lints {
+  #![deny(unreachable_alternative, useless_chaining)]
+  #![allow(unused_rule)]
+
+  keyword = "let" !ident_char
+          / "in" !ident_char
+
+  #[allow(unreachable_alternative)]
+  identifier = ident_char+
+             / "let" // is not reported
+
+  ident_char = ["a-z"]
+
+  // The number is parsed again when the first alternative fails.
+  #[allow(backtracking)]
+  sum = number "+" sum
+      / number
+
+  number = ["0-9"]+
+
+  // numbers = (number+ ","?)* // is detected: a number repeated by `number+` can also start the next iteration.
+
+  // Stops the compilation:
+  // shadowed = "a" / "ab"
+}
+
+#[test]
+fn test_lints() {
+  use oak_runtime::*;
+
+  assert!(lints::recognize_keyword("let".into_state()).is_successful());
+  assert!(lints::recognize_identifier("letter".into_state()).is_successful());
+  assert!(lints::recognize_sum("1+2+3".into_state()).is_successful());
+}
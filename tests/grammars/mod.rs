@@ -23,4 +23,24 @@ pub mod well_formedness;
 pub mod useless_chaining;
 pub mod unreachable_rule;
 pub mod issue94;
+pub mod skip;
+pub mod indentation;
+pub mod bytes;
+pub mod tokens;
+pub mod counted_repeat;
+pub mod capture;
+pub mod collection;
+pub mod labels;
+pub mod derive_ast;
+pub mod type_ascription;
+pub mod generics;
+pub mod auto_box;
+pub mod with_spans;
+pub mod unused;
+pub mod lints;
+pub mod optimizer;
+pub mod left_factoring;
+pub mod structural_duplicate;
+pub mod char_class;
+pub mod grammar_stats;
 mod stream_span;
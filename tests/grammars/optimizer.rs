@@ -0,0 +1,40 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::optimizer::*;
+
+// Every rule is rewritten by the optimizer, the parsed values must be the same.
+grammar! optimizer {
+  #![allow(unused_rule, useless_chaining)]
+
+  word = "l" "e" "t" (letter (letter forward))
+
+  letter = ["a-c"] / ["d-f"] / (["x"] / ["y"])
+
+  forward = letter
+
+  keyword = "let" !!" "
+}
+
+#[test]
+fn test_optimizer() {
+  use oak_runtime::*;
+
+  assert_eq!(optimizer::parse_letter("e".into_state()).unwrap_data(), 'e');
+  assert_eq!(optimizer::parse_letter("y".into_state()).unwrap_data(), 'y');
+  assert!(optimizer::parse_letter("z".into_state()).is_failed());
+  assert_eq!(optimizer::parse_word("letaex".into_state()).unwrap_data(), ('a', 'e', 'x'));
+  assert!(optimizer::recognize_keyword("let ".into_state()).is_successful());
+  assert!(optimizer::recognize_keyword("letx".into_state()).is_failed());
+}
@@ -0,0 +1,57 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::skip::*;
+
+grammar! skip {
+  #![skip(spacing)]
+
+  program = spacing let_binding+
+
+  let_binding = "let" ident "=" number ";" > make_binding
+
+  #[atomic]
+  ident = ["a-zA-Z_"]+ > to_string
+
+  #[atomic]
+  number = ["0-9"]+ > to_number
+
+  spacing = [" \n\t"]* -> (^)
+
+  pub type Binding = (String, u32);
+
+  fn make_binding(name: String, value: u32) -> Binding {
+    (name, value)
+  }
+
+  fn to_string(raw_text: Vec<char>) -> String {
+    raw_text.into_iter().collect()
+  }
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    to_string(raw_text).parse().unwrap()
+  }
+}
+
+#[test]
+fn test_skip() {
+  use oak_runtime::*;
+
+  let state = skip::parse_program("  let x = 1;\nlet  y=42 ;  ".into_state());
+  let data = state.unwrap_data();
+  assert_eq!(data, vec![(format!("x"), 1), (format!("y"), 42)]);
+
+  // `ident` is atomic so no spacing is accepted inside an identifier.
+  assert!(skip::parse_program("let x y = 1;".into_state()).is_failed());
+}
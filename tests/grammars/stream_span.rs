@@ -25,6 +25,8 @@ grammar! stream_span {
 
   span_a = .. "a"
 
+  letters = "x" ..["a-z"]+ "y"
+
   use oak_runtime::str_stream::*;
 
   pub struct Expr {
@@ -57,4 +59,8 @@ fn test_stream_span() {
   assert_eq!(data.full_sp, make_span(0, 4));
   assert_eq!(data.span_a, make_span(0, 1));
   assert_eq!(data.c3_sp, make_span(2, 3));
+
+  let (letters_sp, letters) = stream_span::parse_letters("xaby".into_state()).unwrap_data();
+  assert_eq!(letters_sp, make_span(1, 3));
+  assert_eq!(letters, vec!['a', 'b']);
 }
@@ -0,0 +1,53 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::structural_duplicate::*;
+
+grammar! structural_duplicate {
+  #![deny(duplicate_rule, duplicate_alternative)]
+
+  list = "[" (number ("," number)*)? "]"
+
+  // Equivalent to `number` up to the action and the called rule:
+  // integer = digit_char+ > to_string // is detected
+
+  number = digit+ > to_number
+
+  digit = ["0-9"]
+  // digit_char = ["0-9"] // is detected
+
+  // Equivalent recursive rules are detected:
+  // nested_a = "(" nested_a ")" / "x"
+  // nested_b = "(" nested_b ")" / "x" // is detected
+
+  // The third alternative is never chosen, it is only reported by `unreachable_alternative`:
+  // sign = "+" > plus / "-" > minus / "+" > minus // is detected
+
+  // Not duplicates: the literals differ.
+  plus_op = "+" digit
+  minus_op = "-" digit
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    raw_text.into_iter().collect::<String>().parse().unwrap()
+  }
+}
+
+#[test]
+fn test_structural_duplicate() {
+  use oak_runtime::*;
+
+  assert!(structural_duplicate::recognize_list("[1,22,3]".into_state()).is_successful());
+  assert!(structural_duplicate::recognize_plus_op("+1".into_state()).is_successful());
+  assert!(structural_duplicate::recognize_minus_op("-1".into_state()).is_successful());
+}
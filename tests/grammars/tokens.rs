@@ -0,0 +1,61 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::tokens::*;
+
+grammar! tokens {
+  type Stream<'a> = TokenStream<'a, Token>;
+
+  sum = number (Token::Plus -> (^) number)* > add
+
+  number = Token::Number(_) > to_number
+
+  // The pattern has a binding, the whole token is still produced.
+  numbers = Token::Number(n)+
+
+  use oak_runtime::token_stream::*;
+
+  #[derive(Clone, Debug, PartialEq)]
+  pub enum Token {
+    Number(u32),
+    Plus
+  }
+
+  fn to_number(token: Token) -> u32 {
+    match token {
+      Token::Number(n) => n,
+      _ => unreachable!()
+    }
+  }
+
+  fn add(first: u32, rest: Vec<u32>) -> u32 {
+    rest.into_iter().fold(first, |sum, n| sum + n)
+  }
+}
+
+#[test]
+fn test_tokens() {
+  use oak_runtime::*;
+
+  let input = vec![Token::Number(1), Token::Plus, Token::Number(41)];
+  let state = tokens::parse_sum(TokenStream::new(&input).into_state());
+  assert_eq!(state.unwrap_data(), 42);
+
+  let input = vec![Token::Plus, Token::Number(1)];
+  assert!(tokens::parse_sum(TokenStream::new(&input).into_state()).is_failed());
+
+  let input = vec![Token::Number(1), Token::Number(2), Token::Plus];
+  let state = tokens::parse_numbers(TokenStream::new(&input).into_state());
+  assert_eq!(state.unwrap_data(), vec![Token::Number(1), Token::Number(2)]);
+}
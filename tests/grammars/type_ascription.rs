@@ -0,0 +1,52 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::type_ascription::*;
+
+grammar! type_ascription {
+  number: u32 = digits > to_number
+
+  digits = ["0-9"]+
+
+  wide: u64 = number
+
+  pair = number "," number -> Pair
+
+  boxed = number -> Box<u32>
+
+  use std::str::FromStr;
+
+  #[derive(Debug, PartialEq)]
+  pub struct Pair(pub u32, pub u32);
+
+  impl From<(u32, u32)> for Pair {
+    fn from((a, b): (u32, u32)) -> Pair {
+      Pair(a, b)
+    }
+  }
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    u32::from_str(&*raw_text.into_iter().collect::<String>()).unwrap()
+  }
+}
+
+#[test]
+fn test_type_ascription() {
+  use oak_runtime::*;
+
+  let wide: u64 = type_ascription::parse_wide("42".into_state()).unwrap_data();
+  assert_eq!(wide, 42);
+  assert_eq!(type_ascription::parse_pair("1,2".into_state()).unwrap_data(), Pair(1, 2));
+  assert_eq!(type_ascription::parse_boxed("7".into_state()).unwrap_data(), Box::new(7));
+}
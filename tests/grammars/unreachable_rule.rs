@@ -65,4 +65,18 @@ grammar! unreachable_rule{
 
     // test12 = .
     //         /"a" // is detected
+
+    // test13 = identifier
+    //         / "let" // is detected
+    //
+    // test14 = ["a-z"]+
+    //         / "b" ["0-9"] // is detected
+    //
+    // test15 = "a" !"b"
+    //         / "ac" // is detected
+
+    test16 = "let" !["a-z"]
+            / identifier
+
+    identifier = ["a-zA-Z_"] ["a-zA-Z0-9_"]*
 }
@@ -0,0 +1,61 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::unused::*;
+
+grammar! unused {
+  #![skip(spacing)]
+
+  #[entry]
+  assignment = identifier "=" number > make_assignment
+
+  #[entry]
+  number = digits > to_number
+
+  #[atomic]
+  identifier = ["a-z"]+ > to_string
+
+  #[atomic]
+  digits = ["0-9"]+
+
+  spacing = " "* -> (^)
+
+  // semicolon = ";" // is detected
+
+  // fn unused_action() {} // is detected
+
+  fn make_assignment(name: String, value: u32) -> (String, u32) {
+    (name, value)
+  }
+
+  fn to_string(raw_text: Vec<char>) -> String {
+    raw_text.into_iter().collect()
+  }
+
+  fn to_number(raw_text: Vec<char>) -> u32 {
+    parse_u32(to_string(raw_text))
+  }
+
+  fn parse_u32(text: String) -> u32 {
+    text.parse().unwrap()
+  }
+}
+
+#[test]
+fn test_unused() {
+  use oak_runtime::*;
+
+  assert_eq!(unused::parse_assignment("x = 10".into_state()).unwrap_data(), (String::from("x"), 10));
+  assert_eq!(unused::parse_number("42".into_state()).unwrap_data(), 42);
+}
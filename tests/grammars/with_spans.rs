@@ -0,0 +1,72 @@
+// Copyright 2018 Pierre Talbot (IRCAM)
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub use self::with_spans::*;
+pub use self::span_marker::*;
+
+grammar! with_spans {
+  #![with_spans]
+
+  sum = number "+" number > make_sum
+
+  number = ["0-9"]+ > to_number
+
+  use oak_runtime::str_stream::*;
+  use std::str::FromStr;
+
+  #[derive(Debug, PartialEq)]
+  pub struct Number {
+    pub span: Span,
+    pub value: u32
+  }
+
+  #[derive(Debug, PartialEq)]
+  pub struct Sum {
+    pub span: Span,
+    pub left: Number,
+    pub right: Number
+  }
+
+  fn make_sum(span: Span, left: Number, right: Number) -> Sum {
+    Sum { span: span, left: left, right: right }
+  }
+
+  fn to_number(span: Span, raw_text: Vec<char>) -> Number {
+    Number {
+      span: span,
+      value: u32::from_str(&*raw_text.into_iter().collect::<String>()).unwrap()
+    }
+  }
+}
+
+grammar! span_marker {
+  letters = "(" (["a-z"]+ >@ located) ")"
+
+  use oak_runtime::str_stream::*;
+
+  fn located(span: Span, letters: Vec<char>) -> (Span, usize) {
+    (span, letters.len())
+  }
+}
+
+#[test]
+fn test_with_spans() {
+  use oak_runtime::*;
+
+  let sum = with_spans::parse_sum("1+23".into_state()).unwrap_data();
+  assert_eq!(sum.span, make_span(0, 4));
+  assert_eq!(sum.left, with_spans::Number { span: make_span(0, 1), value: 1 });
+  assert_eq!(sum.right, with_spans::Number { span: make_span(2, 4), value: 23 });
+  assert_eq!(span_marker::parse_letters("(abc)".into_state()).unwrap_data(), (make_span(1, 4), 3));
+}